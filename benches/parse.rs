@@ -0,0 +1,79 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ornithology_cli::archive;
+use std::io::Write;
+
+const TWEETS: usize = 1_000_000;
+
+/// Build an in-memory archive with `n` tweets in `data/tweet.js`.
+///
+/// The tweets are roughly the shape of real archive entries, but without the (large) entity data.
+fn synthetic_archive(n: usize) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("data/tweet.js", zip::write::FileOptions::default())
+        .unwrap();
+    write!(zip, "window.YTD.tweet.part0 = [").unwrap();
+    for i in 0..n {
+        if i != 0 {
+            write!(zip, ",").unwrap();
+        }
+        write!(
+            zip,
+            r#"
+  {{
+    "tweet" : {{
+      "retweeted" : false,
+      "source" : "<a href=\"https://mobile.twitter.com\" rel=\"nofollow\">Twitter Web App</a>",
+      "entities" : {{ "hashtags" : [ ], "symbols" : [ ], "user_mentions" : [ ], "urls" : [ ] }},
+      "display_text_range" : [ "0", "42" ],
+      "favorite_count" : "{likes}",
+      "id_str" : "{id}",
+      "truncated" : false,
+      "retweet_count" : "{rts}",
+      "id" : "{id}",
+      "created_at" : "Wed Oct 10 20:19:24 +0000 2018",
+      "favorited" : false,
+      "full_text" : "This is synthetic tweet number {i}, which is rather dull.",
+      "lang" : "en"
+    }}
+  }}"#,
+            id = 1_000_000_000_000_000_000u64 + i as u64,
+            likes = i % 97,
+            rts = i % 13,
+            i = i,
+        )
+        .unwrap();
+    }
+    write!(zip, "\n]").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+fn parse_tweets(c: &mut Criterion) {
+    let bytes = synthetic_archive(TWEETS);
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.measurement_time(std::time::Duration::from_secs(60));
+    group.throughput(Throughput::Elements(TWEETS as u64));
+    group.bench_function("1M tweets", |b| {
+        b.iter_batched(
+            || {
+                let zip = zip::ZipArchive::new(std::io::Cursor::new(&bytes[..])).unwrap();
+                archive::Archive::new(zip).unwrap()
+            },
+            |mut archive| {
+                let likes: Vec<usize> = archive::parse(
+                    &mut archive,
+                    archive::DataType::Tweets,
+                    |archive::Tweet::One(tweet)| Some(tweet.likes),
+                )
+                .unwrap();
+                likes
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_tweets);
+criterion_main!(benches);
@@ -35,7 +35,7 @@ impl RawClient {
         .set_redirect_uri(redirect);
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
         let (auth_url, csrf_token) = client
-            .authorize_url(|| CsrfToken::new_random())
+            .authorize_url(CsrfToken::new_random)
             .add_scope(Scope::new("tweet.read".to_string()))
             .add_scope(Scope::new("users.read".to_string()))
             .add_scope(Scope::new("follows.read".to_string()))
@@ -70,11 +70,9 @@ impl RawClient {
             Err(oauth2::RequestTokenError::ServerResponse(r)) => {
                 let e = Err(anyhow::anyhow!(r.error().clone()));
                 match (r.error_description(), r.error_uri()) {
-                    (Some(desc), Some(url)) => {
-                        e.context(url.to_string()).context(desc.to_string()).into()
-                    }
-                    (Some(desc), None) => e.context(desc.to_string()).into(),
-                    (None, Some(url)) => e.context(url.to_string()).into(),
+                    (Some(desc), Some(url)) => e.context(url.to_string()).context(desc.to_string()),
+                    (Some(desc), None) => e.context(desc.to_string()),
+                    (None, Some(url)) => e.context(url.to_string()),
                     (None, None) => e,
                 }
             }
@@ -102,7 +100,7 @@ impl tower::Service<reqwest::RequestBuilder> for RawClient {
     fn call(&mut self, req: reqwest::RequestBuilder) -> Self::Future {
         use oauth2::TokenResponse;
         let fut = req.bearer_auth(self.oauth.access_token().secret()).send();
-        Box::pin(async move { Ok(fut.await.context("request")?) })
+        Box::pin(async move { fut.await.context("request") })
     }
 }
 
@@ -284,7 +282,7 @@ impl Client {
     {
         page_at_a_time!(
             self,
-            "Fetch followers",
+            "Fetch users",
             ids,
             100,
             tower::limit::rate::Rate::new(900, std::time::Duration::from_secs(15 * 60)),
@@ -364,7 +362,7 @@ pub struct WhoAmI {
     pub username: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicTweetMetrics {
     #[serde(rename = "retweet_count")]
     pub retweets: usize,
@@ -388,16 +386,7 @@ pub struct Tweet {
     // would be great to read non_public_metrics, but those aren't available >30 days
 }
 
-impl Tweet {
-    pub fn goodness(&self) -> usize {
-        self.metrics.likes
-            + 2 * self.metrics.retweets
-            + 3 * self.metrics.quotations
-            + self.metrics.replies / 2
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicUserMetrics {
     #[serde(rename = "followers_count")]
     pub followers: usize,
@@ -405,13 +394,25 @@ pub struct PublicUserMetrics {
     pub following: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
+    #[serde(rename = "id", with = "u64_but_str")]
+    pub id: u64,
     pub username: String,
     #[serde(rename = "public_metrics")]
     pub metrics: PublicUserMetrics,
 }
 
+impl User {
+    /// How notable it is to have a follow relationship with this user.
+    ///
+    /// An account with lots of followers is neat, unless it also follows lots of accounts (at which
+    /// point it probably just follows everyone back).
+    pub fn neatness(&self) -> isize {
+        self.metrics.followers as isize - 10 * self.metrics.following as isize
+    }
+}
+
 // Keeping this around for if I ever need to add pagination.
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -422,7 +423,7 @@ struct Meta {
     next: Option<String>,
 }
 
-mod u64_but_str {
+pub(crate) mod u64_but_str {
     use std::fmt::Display;
 
     use serde::{de, Deserialize, Deserializer, Serializer};
@@ -1,64 +1,301 @@
 use serde::Deserialize;
 
-pub fn parse<R, T, F, FT, C>(
-    archive: &mut zip::ZipArchive<R>,
-    datafile: &str,
+mod account;
+mod dm;
+mod manifest;
+mod media;
+mod source;
+mod tweet;
+pub use account::Account;
+pub use dm::{
+    Conversation, ConversationData, Event, Join, Message, NameUpdate, ParticipantsChange, Reaction,
+};
+pub use manifest::{
+    ArchiveInfo, ArchiveManifest, DataFile, DataType, DataTypeFiles, Format, UserInfo,
+};
+pub use media::MediaFile;
+pub use source::{Directory, Source};
+pub use tweet::{
+    Entities, ExtendedEntities, Hashtag, Kind, Media, MediaKind, RetweetOf, RetweetedStatus,
+    RetweetedUser, Tweet, TweetData, TweetRef, Url, UserMention,
+};
+
+/// Open the Twitter archive at `path`.
+///
+/// `path` may be either the archive .zip file as downloaded from Twitter, or a directory that it
+/// has been extracted into (see [`Directory`]).
+pub fn open(path: &std::path::Path) -> anyhow::Result<Archive<Box<dyn Source>>> {
+    use anyhow::Context;
+    let source: Box<dyn Source> = if path.is_dir() {
+        Box::new(Directory::new(path).context("open twitter archive as directory")?)
+    } else {
+        let zipfile = std::fs::File::open(path).context("open twitter archive")?;
+        Box::new(zip::ZipArchive::new(zipfile).context("open twitter archive as zip")?)
+    };
+    Archive::new(source)
+}
+
+/// A Twitter archive, along with its manifest if it has one.
+pub struct Archive<S> {
+    source: S,
+    manifest: Option<ArchiveManifest>,
+}
+
+impl<S> Archive<S>
+where
+    S: Source,
+{
+    pub fn new(mut source: S) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        const MANIFEST: &str = "data/manifest.js";
+        let manifest = match source.open(MANIFEST) {
+            Ok(file) => {
+                let mut file = std::io::BufReader::new(file);
+                // Unlike the other data files, the manifest is a single object, not an array.
+                skip_to(&mut file, b'{')
+                    .with_context(|| format!("find {{ indicating start of data in {}", MANIFEST))?;
+                Some(serde_json::from_reader(file).with_context(|| format!("parse {}", MANIFEST))?)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("pick {} from archive", MANIFEST)),
+        };
+        Ok(Self { source, manifest })
+    }
+
+    pub fn manifest(&self) -> Option<&ArchiveManifest> {
+        self.manifest.as_ref()
+    }
+
+    pub fn format(&self) -> Format {
+        match &self.manifest {
+            None => Format::Unversioned,
+            Some(m) => Format::Manifest {
+                generated: m.archive_info.generated,
+            },
+        }
+    }
+
+    /// Whether the archive holds any data of the given kind.
+    pub fn has(&self, kind: DataType) -> bool {
+        !self.datafiles(kind).is_empty()
+    }
+
+    /// Find all the files in the archive that hold the given kind of data, in order.
+    ///
+    /// If the archive has a manifest, this is just what the manifest says. Otherwise, we go by
+    /// naming convention, keeping in mind that larger archives split their data across multiple
+    /// files, so tweets may be in `data/tweet.js`, `data/tweet-part1.js`, `data/tweet-part2.js`,
+    /// and so on.
+    pub fn datafiles(&self, kind: DataType) -> Vec<String> {
+        if let Some(manifest) = &self.manifest {
+            return manifest.files(kind).map(|f| f.name.clone()).collect();
+        }
+
+        for name in kind.conventional_names() {
+            let single = format!("data/{}.js", name);
+            let part_prefix = format!("data/{}-part", name);
+            let mut files: Vec<(usize, &str)> = self
+                .source
+                .file_names()
+                .filter_map(|f| {
+                    if f == single {
+                        // the first file doesn't have a part number, so it comes first
+                        return Some((0, f));
+                    }
+                    let part = f.strip_prefix(&part_prefix)?.strip_suffix(".js")?;
+                    Some((part.parse().ok()?, f))
+                })
+                .collect();
+            if !files.is_empty() {
+                files.sort_unstable();
+                return files.into_iter().map(|(_, f)| f.to_string()).collect();
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// Parse every entry of the given kind of data from the archive.
+///
+/// If the data is split across multiple files (see [`Archive::datafiles`]), the entries of all
+/// the files are parsed as though they were in a single file.
+pub fn parse<S, T, F, FT, C>(
+    archive: &mut Archive<S>,
+    kind: DataType,
     mut map: F,
 ) -> anyhow::Result<C>
 where
-    R: std::io::Read + std::io::Seek,
+    S: Source,
     T: serde::de::DeserializeOwned,
     F: FnMut(T) -> Option<FT>,
-    C: FromIterator<FT>,
+    C: Default + Extend<FT>,
 {
     use anyhow::Context;
-    let mut file = archive
-        .by_name(datafile)
-        .with_context(|| format!("pick {} from archive", datafile))?;
-    let mut contents = String::new();
-    std::io::Read::read_to_string(&mut file, &mut contents)
-        .with_context(|| format!("read {}", datafile))?;
-
-    let data_start = contents
-        .find('[')
-        .with_context(|| format!("find [ indicating start of data in {}", datafile))?;
-    let data = &contents[data_start..];
-    let mut data = data.as_bytes();
-    let deser = serde_json_array_iter::iter_json_array(&mut data);
-    Ok(deser
-        .filter_map(|v| v.map(&mut map).transpose())
-        .collect::<Result<C, std::io::Error>>()
-        .with_context(|| format!("parse {}", datafile))?)
+    let datafiles = archive.datafiles(kind);
+    anyhow::ensure!(!datafiles.is_empty(), "no {} data in archive", kind);
+
+    let mut all = C::default();
+    for datafile in datafiles {
+        let file = archive
+            .source
+            .open(&datafile)
+            .with_context(|| format!("pick {} from archive", datafile))?;
+
+        // The data files can be huge, so we don't want to read them into memory in full. Instead,
+        // we skip past the `window.YTD.tweet.part0 = ` bit and then stream array elements
+        // straight out of the file.
+        let mut file = std::io::BufReader::new(file);
+        skip_to(&mut file, b'[')
+            .with_context(|| format!("find [ indicating start of data in {}", datafile))?;
+        let deser = serde_json_array_iter::iter_json_array(file);
+
+        // We want to stream straight into `all`, but also stop at (and report) the first error.
+        let mut error = None;
+        all.extend(
+            deser
+                .map_while(|v| v.map_err(|e| error = Some(e)).ok())
+                .filter_map(&mut map),
+        );
+        if let Some(e) = error {
+            return Err(e).with_context(|| format!("parse {}", datafile));
+        }
+    }
+    Ok(all)
+}
+
+/// Advance `reader` until the next byte it yields is `needle`.
+fn skip_to(reader: &mut impl std::io::BufRead, needle: u8) -> std::io::Result<()> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        match buf.iter().position(|&b| b == needle) {
+            Some(at) => {
+                reader.consume(at);
+                return Ok(());
+            }
+            None => {
+                let n = buf.len();
+                reader.consume(n);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub enum Follower {
     #[serde(rename = "follower")]
     One {
-        #[serde(rename = "accountId", deserialize_with = "u64_from_str")]
+        #[serde(rename = "accountId", deserialize_with = "from_str")]
         id: u64,
     },
 }
 
 #[derive(Debug, Deserialize)]
-pub enum Tweet {
-    #[serde(rename = "tweet")]
+pub enum Following {
+    #[serde(rename = "following")]
     One {
-        #[serde(rename = "id", deserialize_with = "u64_from_str")]
+        #[serde(rename = "accountId", deserialize_with = "from_str")]
         id: u64,
-        #[serde(rename = "full_text")]
-        text: String,
     },
 }
 
-fn u64_from_str<'de, D>(deserializer: D) -> Result<u64, D::Error>
+#[derive(Debug, Deserialize)]
+pub enum Like {
+    #[serde(rename = "like")]
+    One(LikeData),
+}
+
+/// A tweet that you liked.
+///
+/// Note that the archive does not record _when_ you liked each tweet, though the likes are
+/// listed with the most recent ones first.
+#[derive(Debug, Deserialize)]
+pub struct LikeData {
+    #[serde(rename = "tweetId", deserialize_with = "from_str")]
+    pub id: u64,
+    /// Missing if the tweet has since been deleted or made private.
+    #[serde(rename = "fullText", default)]
+    pub text: Option<String>,
+    #[serde(rename = "expandedUrl")]
+    pub url: String,
+}
+
+impl LikeData {
+    /// The username of the author of the liked tweet, if the archive knows it.
+    ///
+    /// Most likes are recorded with a `twitter.com/i/web/status/` link, which doesn't say who the
+    /// author was, so this is frequently `None`.
+    pub fn author(&self) -> Option<String> {
+        TweetRef::from_url(&self.url)
+            .map(|r| r.author)
+            .filter(|author| author != "i")
+    }
+
+    /// When the liked tweet was posted (not when it was liked).
+    pub fn created(&self) -> Option<time::OffsetDateTime> {
+        snowflake_time(self.id)
+    }
+}
+
+/// Extract the creation time embedded in a Twitter "snowflake" id.
+///
+/// Only ids generated after November 2010 are snowflakes, so this returns `None` for older ids.
+/// <https://developer.twitter.com/en/docs/twitter-ids>
+pub fn snowflake_time(id: u64) -> Option<time::OffsetDateTime> {
+    // Twitter's custom epoch, in milliseconds since the Unix epoch.
+    const TWEPOCH: i128 = 1288834974657;
+    // The first snowflake id; anything before it was sequential.
+    const FIRST_SNOWFLAKE: u64 = 29700859247;
+    if id < FIRST_SNOWFLAKE {
+        return None;
+    }
+    let ms = TWEPOCH + (id >> 22) as i128;
+    time::OffsetDateTime::from_unix_timestamp_nanos(ms * 1_000_000).ok()
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: serde::de::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
 {
     let s = String::deserialize(deserializer)?;
     s.parse().map_err(serde::de::Error::custom)
 }
 
+fn option_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// The archive uses the "classic" Twitter API timestamp format, like
+/// `Wed Oct 10 20:19:24 +0000 2018`, rather than RFC3339.
+mod archive_date {
+    use serde::{de, Deserialize, Deserializer};
+    use time::format_description::FormatItem;
+    use time::macros::format_description;
+
+    const FORMAT: &[FormatItem<'static>] = format_description!(
+        "[weekday repr:short] [month repr:short] [day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute] [year]"
+    );
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<time::OffsetDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        time::OffsetDateTime::parse(&s, FORMAT).map_err(de::Error::custom)
+    }
+}
+
 // https://github.com/serde-rs/json/issues/404
 mod serde_json_array_iter {
     use serde::de::DeserializeOwned;
@@ -0,0 +1,106 @@
+use super::{from_str, parse, Archive, DataType, Source};
+use serde::Deserialize;
+
+/// The account an archive belongs to.
+///
+/// This is assembled from `data/account.js` and (if present) `data/profile.js`.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub id: u64,
+    pub username: String,
+    pub display_name: String,
+    pub created: time::OffsetDateTime,
+    pub bio: String,
+    /// Note that this is usually a `t.co` link.
+    pub website: String,
+    pub location: String,
+    pub avatar_url: Option<String>,
+    pub header_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+enum AccountEntry {
+    #[serde(rename = "account")]
+    One {
+        #[serde(rename = "accountId", deserialize_with = "from_str")]
+        id: u64,
+        username: String,
+        #[serde(rename = "accountDisplayName", default)]
+        display_name: String,
+        #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+        created: time::OffsetDateTime,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+enum ProfileEntry {
+    #[serde(rename = "profile")]
+    One {
+        #[serde(default)]
+        description: Description,
+        #[serde(rename = "avatarMediaUrl")]
+        avatar_url: Option<String>,
+        #[serde(rename = "headerMediaUrl")]
+        header_url: Option<String>,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Description {
+    #[serde(default)]
+    bio: String,
+    #[serde(default)]
+    website: String,
+    #[serde(default)]
+    location: String,
+}
+
+impl<S> Archive<S>
+where
+    S: Source,
+{
+    /// Read out who this archive belongs to.
+    pub fn account(&mut self) -> anyhow::Result<Account> {
+        use anyhow::Context;
+        let mut accounts: Vec<AccountEntry> =
+            parse(self, DataType::Account, Some).context("extract account")?;
+        let AccountEntry::One {
+            id,
+            username,
+            display_name,
+            created,
+        } = accounts.pop().context("archive has no account")?;
+
+        let mut account = Account {
+            id,
+            username,
+            display_name,
+            created,
+            bio: String::new(),
+            website: String::new(),
+            location: String::new(),
+            avatar_url: None,
+            header_url: None,
+        };
+
+        // The profile is nice to have, but we can do without.
+        if self.has(DataType::Profile) {
+            let mut profiles: Vec<ProfileEntry> =
+                parse(self, DataType::Profile, Some).context("extract profile")?;
+            if let Some(ProfileEntry::One {
+                description,
+                avatar_url,
+                header_url,
+            }) = profiles.pop()
+            {
+                account.bio = description.bio;
+                account.website = description.website;
+                account.location = description.location;
+                account.avatar_url = avatar_url;
+                account.header_url = header_url;
+            }
+        }
+
+        Ok(account)
+    }
+}
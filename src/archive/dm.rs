@@ -0,0 +1,185 @@
+use super::{from_str, option_from_str};
+use serde::Deserialize;
+
+/// A single entry in the archive's direct message data.
+///
+/// One-on-one conversations live in `data/direct-messages.js`, and group conversations in
+/// `data/direct-messages-group.js`, but both use the same format.
+#[derive(Debug, Deserialize)]
+pub enum Conversation {
+    #[serde(rename = "dmConversation")]
+    One(ConversationData),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationData {
+    /// For one-on-one conversations, this is the two participants' ids joined by a `-`.
+    #[serde(rename = "conversationId")]
+    pub id: String,
+    /// Everything that happened in the conversation, most recent first.
+    #[serde(rename = "messages")]
+    pub events: Vec<Event>,
+}
+
+impl ConversationData {
+    /// All the messages in the conversation, most recent first.
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.events.iter().filter_map(|e| match e {
+            Event::MessageCreate(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// The ids of everyone who has taken part in the conversation.
+    pub fn participants(&self) -> std::collections::BTreeSet<u64> {
+        let mut participants = std::collections::BTreeSet::new();
+        for event in &self.events {
+            match event {
+                Event::MessageCreate(m) => {
+                    participants.insert(m.sender_id);
+                    participants.extend(m.recipient_id);
+                }
+                Event::JoinConversation(j) => {
+                    participants.extend(&j.participants_snapshot);
+                }
+                Event::ParticipantsJoin(p) | Event::ParticipantsLeave(p) => {
+                    participants.extend(&p.user_ids);
+                }
+                Event::ConversationNameUpdate(_) | Event::Other => {}
+            }
+        }
+        participants
+    }
+
+    /// Whether this is a group conversation rather than a one-on-one one.
+    pub fn is_group(&self) -> bool {
+        // Group messages don't have a recipient, since they go to everyone.
+        !self.id.contains('-') || self.messages().any(|m| m.recipient_id.is_none())
+    }
+}
+
+#[derive(Debug)]
+pub enum Event {
+    MessageCreate(Message),
+    /// You were added to a group conversation.
+    JoinConversation(Join),
+    ParticipantsJoin(ParticipantsChange),
+    ParticipantsLeave(ParticipantsChange),
+    ConversationNameUpdate(NameUpdate),
+    /// Any other kind of event, like a reaction or an update to the conversation's avatar.
+    Other,
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // This is what `#[derive(Deserialize)]` would do, except that unknown event types are
+        // skipped rather than rejected. `#[serde(other)]` can't do that, since it only works for
+        // variants without data.
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Event;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a direct message event")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Event, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let kind: String = map
+                    .next_key()?
+                    .ok_or_else(|| serde::de::Error::custom("empty direct message event"))?;
+                let event = match &*kind {
+                    "messageCreate" => Event::MessageCreate(map.next_value()?),
+                    "joinConversation" => Event::JoinConversation(map.next_value()?),
+                    "participantsJoin" => Event::ParticipantsJoin(map.next_value()?),
+                    "participantsLeave" => Event::ParticipantsLeave(map.next_value()?),
+                    "conversationNameUpdate" => Event::ConversationNameUpdate(map.next_value()?),
+                    _ => {
+                        map.next_value::<serde::de::IgnoredAny>()?;
+                        Event::Other
+                    }
+                };
+                Ok(event)
+            }
+        }
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    #[serde(deserialize_with = "from_str")]
+    pub id: u64,
+    #[serde(deserialize_with = "from_str")]
+    pub sender_id: u64,
+    /// Not set for messages in group conversations.
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub recipient_id: Option<u64>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+    #[serde(default)]
+    pub media_urls: Vec<String>,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reaction {
+    #[serde(deserialize_with = "from_str")]
+    pub sender_id: u64,
+    /// Like `like`, `funny`, or `agree`.
+    pub reaction_key: String,
+    #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Join {
+    #[serde(deserialize_with = "from_str")]
+    pub initiating_user_id: u64,
+    #[serde(default, deserialize_with = "ids_from_strs")]
+    pub participants_snapshot: Vec<u64>,
+    #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantsChange {
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub initiating_user_id: Option<u64>,
+    #[serde(default, deserialize_with = "ids_from_strs")]
+    pub user_ids: Vec<u64>,
+    #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameUpdate {
+    #[serde(deserialize_with = "from_str")]
+    pub initiating_user_id: u64,
+    pub name: String,
+    #[serde(rename = "createdAt", with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+}
+
+fn ids_from_strs<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
@@ -0,0 +1,157 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A logical kind of data stored in a Twitter archive.
+///
+/// Each kind may be spread across several files in the archive. Use [`super::Archive::datafiles`]
+/// to find them all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Account,
+    DirectMessages,
+    DirectMessagesGroup,
+    Follower,
+    Following,
+    Like,
+    Profile,
+    Tweets,
+}
+
+impl DataType {
+    /// The key this data type is listed under in `data/manifest.js`.
+    pub fn manifest_key(&self) -> &'static str {
+        match self {
+            DataType::Account => "account",
+            DataType::DirectMessages => "directMessages",
+            DataType::DirectMessagesGroup => "directMessagesGroup",
+            DataType::Follower => "follower",
+            DataType::Following => "following",
+            DataType::Like => "like",
+            DataType::Profile => "profile",
+            DataType::Tweets => "tweets",
+        }
+    }
+
+    /// The file names (without the `data/` prefix and `.js` suffix) this data type has been stored
+    /// under in archives that do not have a manifest, in order of preference.
+    pub(super) fn conventional_names(&self) -> &'static [&'static str] {
+        match self {
+            DataType::Account => &["account"],
+            DataType::DirectMessages => &["direct-messages", "direct-message"],
+            DataType::DirectMessagesGroup => &["direct-messages-group", "direct-message-group"],
+            DataType::Follower => &["follower"],
+            DataType::Following => &["following"],
+            DataType::Like => &["like"],
+            DataType::Profile => &["profile"],
+            DataType::Tweets => &["tweet", "tweets"],
+        }
+    }
+
+    /// The directories (under `data/`) media for this data type has been stored in in archives
+    /// that do not have a manifest, in order of preference.
+    pub(super) fn conventional_media_directories(&self) -> &'static [&'static str] {
+        match self {
+            DataType::DirectMessages => &["direct_messages_media", "direct_message_media"],
+            DataType::DirectMessagesGroup => {
+                &["direct_messages_group_media", "direct_message_group_media"]
+            }
+            DataType::Profile => &["profile_media"],
+            DataType::Tweets => &["tweets_media", "tweet_media"],
+            DataType::Account | DataType::Follower | DataType::Following | DataType::Like => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.manifest_key())
+    }
+}
+
+/// Which layout an archive uses.
+///
+/// Twitter doesn't version its archives explicitly, so this is mostly a distinction between
+/// archives that describe themselves and those that don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// An archive without `data/manifest.js`, where file names have to be guessed.
+    Unversioned,
+    /// An archive with a `data/manifest.js` that was generated at the given time.
+    Manifest { generated: time::OffsetDateTime },
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Unversioned => write!(f, "unversioned (no manifest.js)"),
+            Format::Manifest { generated } => {
+                write!(f, "manifest.js, generated {}", generated.date())
+            }
+        }
+    }
+}
+
+/// The contents of `data/manifest.js`, which describes what a (newer) archive contains.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub user_info: UserInfo,
+    pub archive_info: ArchiveInfo,
+    /// All the data types in the archive, keyed by their manifest name (see
+    /// [`DataType::manifest_key`]).
+    pub data_types: HashMap<String, DataTypeFiles>,
+}
+
+impl ArchiveManifest {
+    /// The files that hold the given type of data, in order.
+    pub fn files(&self, kind: DataType) -> impl Iterator<Item = &DataFile> {
+        self.data_types
+            .get(kind.manifest_key())
+            .into_iter()
+            .flat_map(|dt| &dt.files)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    #[serde(deserialize_with = "super::from_str")]
+    pub account_id: u64,
+    #[serde(rename = "userName")]
+    pub username: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveInfo {
+    #[serde(rename = "generationDate", with = "time::serde::rfc3339")]
+    pub generated: time::OffsetDateTime,
+    #[serde(deserialize_with = "super::from_str")]
+    pub size_bytes: u64,
+    #[serde(rename = "isPartialArchive")]
+    pub partial: bool,
+    #[serde(deserialize_with = "super::from_str")]
+    pub max_part_size_bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataTypeFiles {
+    #[serde(default)]
+    pub files: Vec<DataFile>,
+    /// Where in the archive media files associated with this data type are stored, if any.
+    pub media_directory: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataFile {
+    #[serde(rename = "fileName")]
+    pub name: String,
+    /// The JavaScript global the file assigns its data to, like `YTD.tweets.part0`.
+    pub global_name: String,
+    /// The number of entries in the file.
+    #[serde(deserialize_with = "super::from_str")]
+    pub count: usize,
+}
@@ -0,0 +1,110 @@
+use super::{Archive, DataType, Source};
+use std::collections::HashMap;
+
+/// A media file (photo, GIF, or video) stored in the archive.
+///
+/// The archive keeps a copy of everything you uploaded, named after the tweet it was attached to,
+/// like `data/tweets_media/1234567890-AbCdEfGh.jpg`. Note that GIFs are stored as `.mp4` files,
+/// since that's how Twitter serves them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaFile {
+    /// The id of the tweet (or other entry) the media belongs to.
+    pub id: u64,
+    /// Where the file lives in the archive, like `data/tweets_media/123-abc.jpg`.
+    pub name: String,
+}
+
+impl MediaFile {
+    /// The file name without the directory, which is unique within the archive.
+    pub fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    /// The MIME type of the file, going by its extension.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        let (_, ext) = self.name.rsplit_once('.')?;
+        Some(match &*ext.to_ascii_lowercase() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "png" => "image/png",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "mp4" => "video/mp4",
+            "mov" => "video/quicktime",
+            _ => return None,
+        })
+    }
+
+    pub fn is_video(&self) -> bool {
+        self.mime_type().is_some_and(|m| m.starts_with("video/"))
+    }
+}
+
+impl<S> Archive<S>
+where
+    S: Source,
+{
+    /// The directory in the archive that holds media for the given kind of data, if any.
+    pub fn media_directory(&self, kind: DataType) -> Option<String> {
+        if let Some(manifest) = &self.manifest {
+            return manifest
+                .data_types
+                .get(kind.manifest_key())
+                .and_then(|dt| dt.media_directory.clone());
+        }
+
+        kind.conventional_media_directories()
+            .iter()
+            .map(|dir| format!("data/{}/", dir))
+            .find(|dir| {
+                self.source
+                    .file_names()
+                    .any(|f| f.starts_with(dir.as_str()))
+            })
+            .map(|dir| dir.trim_end_matches('/').to_string())
+    }
+
+    /// Find all the media files for the given kind of data, keyed by the id they belong to.
+    ///
+    /// This only looks at file names, so it's cheap even for archives with lots of media. Use
+    /// [`Archive::read`] to get at the contents. Files whose names don't start with an id are
+    /// ignored.
+    pub fn media(&self, kind: DataType) -> HashMap<u64, Vec<MediaFile>> {
+        let mut index: HashMap<u64, Vec<MediaFile>> = HashMap::new();
+        let dir = match self.media_directory(kind) {
+            Some(dir) => format!("{}/", dir.trim_end_matches('/')),
+            None => return index,
+        };
+        for name in self.source.file_names() {
+            let id = name
+                .strip_prefix(&*dir)
+                .and_then(|f| f.split_once('-'))
+                .and_then(|(id, _)| id.parse().ok());
+            if let Some(id) = id {
+                index.entry(id).or_default().push(MediaFile {
+                    id,
+                    name: name.to_string(),
+                });
+            }
+        }
+        // Keep things in a predictable order, which for multiple photos is usually the order they
+        // were attached in.
+        for files in index.values_mut() {
+            files.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        }
+        index
+    }
+
+    /// Read the full contents of a file in the archive.
+    pub fn read(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        use anyhow::Context;
+        use std::io::Read;
+        let mut file = self
+            .source
+            .open(name)
+            .with_context(|| format!("pick {} from archive", name))?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .with_context(|| format!("read {}", name))?;
+        Ok(contents)
+    }
+}
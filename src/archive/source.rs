@@ -0,0 +1,109 @@
+use anyhow::Context;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Somewhere the files of a Twitter archive can be read from.
+///
+/// File names are always relative to the root of the archive, like `data/tweet.js`, regardless of
+/// how the files are actually stored.
+pub trait Source {
+    /// The names of all the files in the archive.
+    fn file_names(&self) -> Box<dyn Iterator<Item = &str> + '_>;
+
+    /// Open the named file for reading.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if there is no such file.
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn io::Read + '_>>;
+}
+
+impl<S> Source for Box<S>
+where
+    S: Source + ?Sized,
+{
+    fn file_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        (**self).file_names()
+    }
+
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn io::Read + '_>> {
+        (**self).open(name)
+    }
+}
+
+/// The archive as Twitter ships it.
+impl<R> Source for zip::ZipArchive<R>
+where
+    R: io::Read + io::Seek,
+{
+    fn file_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(zip::ZipArchive::file_names(self))
+    }
+
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn io::Read + '_>> {
+        match self.by_name(name) {
+            Ok(file) => Ok(Box::new(file)),
+            Err(zip::result::ZipError::FileNotFound) => Err(io::ErrorKind::NotFound.into()),
+            Err(zip::result::ZipError::Io(e)) => Err(e),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/// An archive that has already been extracted.
+///
+/// Since all the data we care about lives in `data/`, this only ever looks in there, so it's fine
+/// if that's the only directory that was kept around.
+#[derive(Debug)]
+pub struct Directory {
+    data: PathBuf,
+    files: Vec<String>,
+}
+
+impl Directory {
+    /// Use the extracted archive at `path`.
+    ///
+    /// `path` can be either the root of the extracted archive, or its `data/` directory.
+    pub fn new(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let data = if path.join("data").is_dir() {
+            path.join("data")
+        } else {
+            path
+        };
+
+        let mut files = Vec::new();
+        walk(&data, "data", &mut files).with_context(|| format!("list {}", data.display()))?;
+        Ok(Self { data, files })
+    }
+}
+
+fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = match name.to_str() {
+            Some(name) => format!("{}/{}", prefix, name),
+            // Twitter doesn't generate non-UTF-8 file names, so this isn't anything of ours.
+            None => continue,
+        };
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), &name, files)?;
+        } else {
+            files.push(name);
+        }
+    }
+    Ok(())
+}
+
+impl Source for Directory {
+    fn file_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.files.iter().map(String::as_str))
+    }
+
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn io::Read + '_>> {
+        let rel = name
+            .strip_prefix("data/")
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let file = std::fs::File::open(self.data.join(rel))?;
+        Ok(Box::new(io::BufReader::new(file)))
+    }
+}
@@ -0,0 +1,457 @@
+use super::{archive_date, from_str, option_from_str};
+use serde::{Deserialize, Serialize};
+
+/// A single entry in the archive's tweet data.
+#[derive(Debug, Deserialize)]
+pub enum Tweet {
+    #[serde(rename = "tweet")]
+    One(TweetData),
+}
+
+/// Everything the archive knows about one of your tweets.
+///
+/// This mirrors the v1.1 API's tweet object, which is what the archive stores, minus fields that
+/// are always empty or meaningless in the archive (like `retweeted` and `favorited`).
+#[derive(Debug, Deserialize)]
+pub struct TweetData {
+    #[serde(deserialize_with = "from_str")]
+    pub id: u64,
+    #[serde(rename = "full_text")]
+    pub text: String,
+    #[serde(rename = "created_at", with = "archive_date")]
+    pub created: time::OffsetDateTime,
+    /// The HTML link to the client the tweet was posted with.
+    ///
+    /// See [`TweetData::source_name`] for just the name of the client.
+    #[serde(default)]
+    pub source: String,
+    /// The [BCP 47](https://tools.ietf.org/html/bcp47) language Twitter detected for the tweet, or
+    /// `und` if it couldn't tell.
+    #[serde(default)]
+    pub lang: String,
+
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub in_reply_to_status_id: Option<u64>,
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub in_reply_to_user_id: Option<u64>,
+    #[serde(default)]
+    pub in_reply_to_screen_name: Option<String>,
+
+    #[serde(default)]
+    pub entities: Entities,
+    /// Holds _all_ the media attached to the tweet, whereas [`Entities::media`] only holds the
+    /// first one. Only present if the tweet has media.
+    #[serde(default)]
+    pub extended_entities: Option<ExtendedEntities>,
+    #[serde(default)]
+    pub possibly_sensitive: bool,
+
+    /// The tweet this is a native retweet of.
+    ///
+    /// Archives from Twitter itself leave this out, but archives built from the API have it. See
+    /// [`TweetData::retweet_of`] for how retweets are found without it.
+    #[serde(default)]
+    pub retweeted_status: Option<RetweetedStatus>,
+
+    #[serde(rename = "favorite_count", deserialize_with = "from_str")]
+    pub likes: usize,
+    #[serde(rename = "retweet_count", deserialize_with = "from_str")]
+    pub retweets: usize,
+}
+
+impl TweetData {
+    /// The name of the client the tweet was posted with, like "Twitter Web App".
+    pub fn source_name(&self) -> &str {
+        // The source is always of the form <a href="..." rel="nofollow">Name</a>.
+        self.source
+            .split_once('>')
+            .and_then(|(_, rest)| rest.split_once('<'))
+            .map(|(name, _)| name)
+            .unwrap_or(&self.source)
+    }
+
+    /// All the media attached to this tweet.
+    pub fn media(&self) -> &[Media] {
+        match &self.extended_entities {
+            Some(extended) => &extended.media,
+            None => &self.entities.media,
+        }
+    }
+
+    /// The text of the tweet as it would be shown on Twitter.
+    ///
+    /// The archive stores the text with `t.co` links and HTML-escaped, so this expands the links
+    /// to where they point, removes the links to attached media (which are shown separately), and
+    /// un-escapes the text.
+    pub fn display_text(&self) -> String {
+        let mut text = self.text.clone();
+        for url in &self.entities.urls {
+            text = text.replace(&url.url, &url.expanded_url);
+        }
+        for media in self.media() {
+            text = text.replace(&media.url, "");
+        }
+        // `&amp;` has to go last so that `&amp;lt;` becomes `&lt;` and not `<`.
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+            .trim_end()
+            .to_string()
+    }
+
+    /// Work out what kind of tweet this is, given the account id of the archive's owner.
+    pub fn kind(&self, me: u64) -> Kind {
+        if let Some(of) = self.retweet_of() {
+            return Kind::Retweet { of };
+        }
+        if let Some(to) = self.in_reply_to_status_id {
+            if self.in_reply_to_user_id == Some(me) {
+                return Kind::SelfReply { to };
+            }
+            return Kind::Reply { to };
+        }
+        if let Some(of) = self.quoted() {
+            return Kind::Quote { of };
+        }
+        Kind::Original
+    }
+
+    /// The tweet this tweet is a native retweet of, if any.
+    ///
+    /// If the archive has [`TweetData::retweeted_status`], that settles it. Otherwise, the archive
+    /// stores native retweets as tweets whose text is `RT @author: text`, which is also what
+    /// people used to type out by hand before native retweets were a thing. The two can mostly be
+    /// told apart because the archive records the original author as a mention starting right
+    /// after the `RT `, and because likes on a native retweet go to the original tweet, so the
+    /// retweet itself never has any. Tweets from before native retweets existed are never native
+    /// retweets either. That still leaves hand-typed `RT @author: ...` tweets from later on that
+    /// nobody liked, which look exactly like native retweets in the archive and so are classified
+    /// as such.
+    ///
+    /// Without `retweeted_status`, the archive only records the original tweet's id if the
+    /// retweet has media, since the media entities point back at the tweet they came from.
+    pub fn retweet_of(&self) -> Option<RetweetOf> {
+        if let Some(rt) = &self.retweeted_status {
+            return Some(RetweetOf {
+                author: rt.user.screen_name.clone(),
+                id: Some(rt.id),
+            });
+        }
+        if !self.text.starts_with("RT @") || self.likes != 0 || self.created < NATIVE_RETWEETS {
+            return None;
+        }
+        let author = self.entities.user_mentions.first()?;
+        if author.indices[0] != 3 {
+            return None;
+        }
+        Some(RetweetOf {
+            author: author.screen_name.clone(),
+            id: self.media().iter().find_map(|m| m.source_status_id),
+        })
+    }
+
+    /// The tweet this tweet quotes, if any.
+    ///
+    /// The archive doesn't record quotes explicitly. Instead, a quote tweet is a tweet whose last
+    /// link is to another tweet.
+    pub fn quoted(&self) -> Option<TweetRef> {
+        self.entities
+            .urls
+            .last()
+            .and_then(|url| TweetRef::from_url(&url.expanded_url))
+    }
+}
+
+/// When Twitter started rolling out native retweets. Anything older that looks like a retweet was
+/// typed out by hand.
+const NATIVE_RETWEETS: time::OffsetDateTime = time::macros::datetime!(2009-11-01 0:00 UTC);
+
+/// The original tweet in [`TweetData::retweeted_status`].
+#[derive(Debug, Deserialize)]
+pub struct RetweetedStatus {
+    #[serde(rename = "id_str", deserialize_with = "from_str")]
+    pub id: u64,
+    pub user: RetweetedUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetweetedUser {
+    pub screen_name: String,
+}
+
+/// What kind of tweet a [`TweetData`] is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Kind {
+    /// A regular tweet.
+    Original,
+    /// A reply to someone else's tweet.
+    Reply { to: u64 },
+    /// A reply to one of your own tweets, usually as part of a thread.
+    SelfReply { to: u64 },
+    /// A native retweet of someone else's tweet.
+    Retweet { of: RetweetOf },
+    /// A tweet that quotes another tweet.
+    Quote { of: TweetRef },
+}
+
+impl Kind {
+    /// A short, human-readable name for this kind of tweet.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Original => "original",
+            Kind::Reply { .. } => "reply",
+            Kind::SelfReply { .. } => "self-reply",
+            Kind::Retweet { .. } => "retweet",
+            Kind::Quote { .. } => "quote",
+        }
+    }
+}
+
+/// The original tweet of a native retweet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetweetOf {
+    pub author: String,
+    /// Only known if the retweeted tweet had media. See [`TweetData::retweet_of`].
+    pub id: Option<u64>,
+}
+
+/// A reference to a (possibly someone else's) tweet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TweetRef {
+    pub author: String,
+    pub id: u64,
+}
+
+impl TweetRef {
+    /// Extract the author and id from a tweet link like `https://twitter.com/jonhoo/status/123`.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let url = url::Url::parse(url).ok()?;
+        if !matches!(
+            url.host_str()?,
+            "twitter.com" | "www.twitter.com" | "mobile.twitter.com" | "x.com"
+        ) {
+            return None;
+        }
+        let mut path = url.path_segments()?;
+        let author = path.next()?;
+        if path.next()? != "status" {
+            return None;
+        }
+        let id = path.next()?.parse().ok()?;
+        Some(Self {
+            author: author.to_string(),
+            id,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://twitter.com/{}/status/{}", self.author, self.id)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Entities {
+    #[serde(default)]
+    pub hashtags: Vec<Hashtag>,
+    #[serde(default)]
+    pub user_mentions: Vec<UserMention>,
+    #[serde(default)]
+    pub urls: Vec<Url>,
+    #[serde(default)]
+    pub media: Vec<Media>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExtendedEntities {
+    #[serde(default)]
+    pub media: Vec<Media>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Hashtag {
+    /// The hashtag, without the leading `#`.
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserMention {
+    /// The id of the mentioned account, or -1 if the account has since been deleted or suspended.
+    #[serde(deserialize_with = "from_str")]
+    pub id: i64,
+    pub screen_name: String,
+    pub name: String,
+    /// Where in the tweet text the mention starts and ends.
+    #[serde(deserialize_with = "indices")]
+    pub indices: [usize; 2],
+}
+
+fn indices<'de, D>(deserializer: D) -> Result<[usize; 2], D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    let [start, end] = <[String; 2]>::deserialize(deserializer)?;
+    Ok([
+        start.parse().map_err(serde::de::Error::custom)?,
+        end.parse().map_err(serde::de::Error::custom)?,
+    ])
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Url {
+    /// The `t.co` link as it appears in the tweet text.
+    pub url: String,
+    pub expanded_url: String,
+    pub display_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Media {
+    #[serde(deserialize_with = "from_str")]
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: MediaKind,
+    /// The `t.co` link as it appears in the tweet text.
+    pub url: String,
+    pub media_url_https: String,
+    pub expanded_url: String,
+    pub display_url: String,
+    /// Set if this media was originally posted in a different tweet.
+    #[serde(default, deserialize_with = "option_from_str")]
+    pub source_status_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Photo,
+    Video,
+    AnimatedGif,
+    /// A kind of media this version doesn't know about.
+    #[serde(other)]
+    Other,
+}
+
+/// Build the API representation of a tweet using only the engagement counts in the archive.
+///
+/// The archive does not know about replies or quote tweets, so those metrics will always be zero.
+/// The like and retweet counts are also only as fresh as the archive itself.
+impl From<&TweetData> for crate::api::Tweet {
+    fn from(tweet: &TweetData) -> Self {
+        crate::api::Tweet {
+            id: tweet.id,
+            created: tweet.created,
+            metrics: crate::api::PublicTweetMetrics {
+                retweets: tweet.retweets,
+                replies: 0,
+                likes: tweet.likes,
+                quotations: 0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a tweet from the archive's JSON, filling in the fields every tweet has.
+    fn parse(extra: &str) -> TweetData {
+        let json = format!(
+            r#"{{
+                "id": "1",
+                "created_at": "Wed Oct 10 20:19:24 +0000 2018",
+                "favorite_count": "0",
+                "retweet_count": "0",
+                {}
+            }}"#,
+            extra
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn mention_of_deleted_account() {
+        let tweet = parse(
+            r#""full_text": "hi @gone",
+               "entities": {
+                 "user_mentions": [
+                   { "id": "-1", "id_str": "-1", "screen_name": "gone", "name": "",
+                     "indices": ["3", "8"] }
+                 ]
+               }"#,
+        );
+        assert_eq!(tweet.entities.user_mentions[0].id, -1);
+        assert_eq!(tweet.entities.user_mentions[0].screen_name, "gone");
+    }
+
+    #[test]
+    fn unknown_media_kind() {
+        let tweet = parse(
+            r#""full_text": "look https://t.co/x",
+               "extended_entities": {
+                 "media": [
+                   { "id": "2", "type": "hologram", "url": "https://t.co/x",
+                     "media_url_https": "https://pbs.twimg.com/media/x.jpg",
+                     "expanded_url": "https://twitter.com/a/status/1/photo/1",
+                     "display_url": "pic.twitter.com/x" }
+                 ]
+               }"#,
+        );
+        assert_eq!(tweet.media()[0].kind, MediaKind::Other);
+    }
+
+    const RT: &str = r#""full_text": "RT @rustlang: Rust 1.0 is out!",
+        "entities": {
+          "user_mentions": [
+            { "id": "2", "screen_name": "rustlang", "name": "Rust", "indices": ["3", "12"] }
+          ]
+        }"#;
+
+    #[test]
+    fn native_retweet() {
+        let tweet = parse(RT);
+        assert_eq!(
+            tweet.retweet_of(),
+            Some(RetweetOf {
+                author: "rustlang".to_string(),
+                id: None,
+            })
+        );
+    }
+
+    #[test]
+    fn manual_retweet_with_likes() {
+        let mut tweet = parse(RT);
+        tweet.likes = 3;
+        assert_eq!(tweet.retweet_of(), None);
+        assert_eq!(tweet.kind(1), Kind::Original);
+    }
+
+    #[test]
+    fn manual_zero_like_retweet() {
+        // Nobody liked it, but native retweets didn't exist yet, so it must have been typed out.
+        let mut tweet = parse(RT);
+        tweet.created = time::macros::datetime!(2009-03-14 15:09 UTC);
+        assert_eq!(tweet.retweet_of(), None);
+        assert_eq!(tweet.kind(1), Kind::Original);
+    }
+
+    #[test]
+    fn retweeted_status() {
+        let tweet = parse(&format!(
+            r#"{},
+            "retweeted_status": {{ "id_str": "42", "user": {{ "screen_name": "rustlang" }} }}"#,
+            RT
+        ));
+        assert_eq!(
+            tweet.kind(1),
+            Kind::Retweet {
+                of: RetweetOf {
+                    author: "rustlang".to_string(),
+                    id: Some(42),
+                }
+            }
+        );
+    }
+}
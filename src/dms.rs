@@ -0,0 +1,294 @@
+//! Statistics about direct messages.
+//!
+//! Everything in here works purely off of the archive, and never talks to the Twitter API.
+
+use crate::archive::{ConversationData, Message};
+use std::collections::HashMap;
+
+/// Gaps between messages longer than this aren't counted as someone taking a while to respond,
+/// but rather as the conversation having ended and later picking back up again.
+const MAX_RESPONSE_TIME: time::Duration = time::Duration::days(7);
+
+#[derive(Debug)]
+pub struct Stats {
+    /// Everyone you've exchanged messages with, and how many messages were exchanged, most first.
+    ///
+    /// For one-on-one conversations this counts messages in both directions, whereas for group
+    /// conversations it only counts the messages that person sent.
+    pub correspondents: Vec<(u64, usize)>,
+    /// All conversations, longest first.
+    pub conversations: Vec<ConversationSummary>,
+    /// The median time it took you to respond in one-on-one conversations.
+    pub my_response_time: Option<time::Duration>,
+    /// The median time it took others to respond to you in one-on-one conversations.
+    pub their_response_time: Option<time::Duration>,
+    /// The number of messages sent in each month (identified by its first day), busiest first.
+    pub busiest_months: Vec<(time::Date, usize)>,
+}
+
+#[derive(Debug)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub group: bool,
+    /// Everyone in the conversation except you.
+    pub others: Vec<u64>,
+    pub messages: usize,
+    pub first: time::OffsetDateTime,
+    pub last: time::OffsetDateTime,
+}
+
+/// Compute DM statistics for the account with id `me`.
+pub fn analyze(me: u64, conversations: &[ConversationData]) -> Stats {
+    let mut correspondents: HashMap<u64, usize> = HashMap::new();
+    let mut summaries = Vec::with_capacity(conversations.len());
+    let mut my_response_times = Vec::new();
+    let mut their_response_times = Vec::new();
+    let mut months: HashMap<time::Date, usize> = HashMap::new();
+
+    for conversation in conversations {
+        // The archive lists messages most recent first, but don't rely on it being consistent.
+        let mut messages: Vec<&Message> = conversation.messages().collect();
+        messages.sort_by_key(|m| (m.created, m.id));
+        let (first, last) = match (messages.first(), messages.last()) {
+            (Some(first), Some(last)) => (first.created, last.created),
+            _ => continue,
+        };
+
+        let group = conversation.is_group();
+        let others: Vec<u64> = conversation
+            .participants()
+            .into_iter()
+            .filter(|&id| id != me)
+            .collect();
+        summaries.push(ConversationSummary {
+            id: conversation.id.clone(),
+            group,
+            others: others.clone(),
+            messages: messages.len(),
+            first,
+            last,
+        });
+
+        for m in &messages {
+            let month = m
+                .created
+                .date()
+                .replace_day(1)
+                .expect("every month has a day 1");
+            *months.entry(month).or_default() += 1;
+        }
+
+        if group {
+            for m in messages.iter().filter(|m| m.sender_id != me) {
+                *correspondents.entry(m.sender_id).or_default() += 1;
+            }
+            continue;
+        }
+
+        if let [other] = others[..] {
+            *correspondents.entry(other).or_default() += messages.len();
+        }
+        for pair in messages.windows(2) {
+            let (prev, next) = (pair[0], pair[1]);
+            if prev.sender_id == next.sender_id {
+                continue;
+            }
+            let took = next.created - prev.created;
+            if took > MAX_RESPONSE_TIME {
+                continue;
+            }
+            if next.sender_id == me {
+                my_response_times.push(took);
+            } else {
+                their_response_times.push(took);
+            }
+        }
+    }
+
+    let mut correspondents: Vec<_> = correspondents.into_iter().collect();
+    correspondents.sort_unstable_by(|(id1, n1), (id2, n2)| n2.cmp(n1).then(id1.cmp(id2)));
+    summaries.sort_unstable_by(|a, b| b.messages.cmp(&a.messages).then(a.first.cmp(&b.first)));
+    let mut busiest_months: Vec<_> = months.into_iter().collect();
+    busiest_months.sort_unstable_by(|(m1, n1), (m2, n2)| n2.cmp(n1).then(m1.cmp(m2)));
+
+    Stats {
+        correspondents,
+        conversations: summaries,
+        my_response_time: median(my_response_times),
+        their_response_time: median(their_response_times),
+        busiest_months,
+    }
+}
+
+fn median(mut durations: Vec<time::Duration>) -> Option<time::Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+    let mid = durations.len() / 2;
+    Some(*durations.select_nth_unstable(mid).1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    const ME: u64 = 1;
+    const START: time::OffsetDateTime = datetime!(2022-01-01 12:00 UTC);
+
+    /// A `messageCreate` event, sent `minutes` after `START`.
+    fn message(id: u64, from: u64, to: Option<u64>, minutes: i64) -> String {
+        let created = (START + time::Duration::minutes(minutes))
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap();
+        let to = to.map_or(String::new(), |to| format!(r#""recipientId": "{}","#, to));
+        format!(
+            r#"{{ "messageCreate": {{ "id": "{}", "senderId": "{}", {} "text": "hi", "createdAt": "{}" }} }}"#,
+            id, from, to, created
+        )
+    }
+
+    fn conversation(id: &str, events: &[String]) -> ConversationData {
+        let json = format!(
+            r#"{{ "conversationId": "{}", "messages": [{}] }}"#,
+            id,
+            events.join(",")
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn empty() {
+        let stats = analyze(ME, &[conversation("1-2", &[])]);
+        assert!(stats.correspondents.is_empty());
+        assert!(stats.conversations.is_empty());
+        assert_eq!(stats.my_response_time, None);
+        assert_eq!(stats.their_response_time, None);
+    }
+
+    #[test]
+    fn one_on_one() {
+        // Most recent first, like in the archive.
+        let stats = analyze(
+            ME,
+            &[conversation(
+                "1-2",
+                &[
+                    message(4, 2, Some(ME), 240),
+                    message(3, ME, Some(2), 180),
+                    message(2, 2, Some(ME), 60),
+                    message(1, ME, Some(2), 0),
+                ],
+            )],
+        );
+        assert_eq!(stats.correspondents, vec![(2, 4)]);
+        assert_eq!(stats.my_response_time, Some(time::Duration::hours(2)));
+        assert_eq!(stats.their_response_time, Some(time::Duration::hours(1)));
+        let summary = &stats.conversations[0];
+        assert!(!summary.group);
+        assert_eq!(summary.others, vec![2]);
+        assert_eq!(summary.messages, 4);
+        assert_eq!(summary.first, START);
+        assert_eq!(summary.last, START + time::Duration::hours(4));
+        assert_eq!(stats.busiest_months, vec![(START.date(), 4)]);
+    }
+
+    #[test]
+    fn one_sided() {
+        let stats = analyze(
+            ME,
+            &[conversation(
+                "1-2",
+                &[
+                    message(3, ME, Some(2), 120),
+                    message(2, ME, Some(2), 60),
+                    message(1, ME, Some(2), 0),
+                ],
+            )],
+        );
+        assert_eq!(stats.correspondents, vec![(2, 3)]);
+        assert_eq!(stats.my_response_time, None);
+        assert_eq!(stats.their_response_time, None);
+    }
+
+    #[test]
+    fn out_of_order() {
+        let stats = analyze(
+            ME,
+            &[conversation(
+                "1-2",
+                &[
+                    message(2, 2, Some(ME), 60),
+                    message(4, 2, Some(ME), 240),
+                    message(1, ME, Some(2), 0),
+                    message(3, ME, Some(2), 180),
+                ],
+            )],
+        );
+        assert_eq!(stats.my_response_time, Some(time::Duration::hours(2)));
+        assert_eq!(stats.their_response_time, Some(time::Duration::hours(1)));
+        assert_eq!(stats.conversations[0].first, START);
+        assert_eq!(
+            stats.conversations[0].last,
+            START + time::Duration::hours(4)
+        );
+    }
+
+    #[test]
+    fn unknown_events() {
+        let reaction = r#"{ "reactionCreate": { "id": "9", "senderId": "2", "reactionKey": "like", "eventId": "1", "createdAt": "2022-01-01T12:05:00.000Z" } }"#;
+        let stats = analyze(
+            ME,
+            &[conversation(
+                "1-2",
+                &[
+                    message(2, 2, Some(ME), 30),
+                    reaction.to_string(),
+                    message(1, ME, Some(2), 0),
+                ],
+            )],
+        );
+        assert_eq!(stats.correspondents, vec![(2, 2)]);
+        assert_eq!(stats.their_response_time, Some(time::Duration::minutes(30)));
+    }
+
+    #[test]
+    fn long_gaps_are_not_responses() {
+        let stats = analyze(
+            ME,
+            &[conversation(
+                "1-2",
+                &[
+                    message(2, 2, Some(ME), 8 * 24 * 60),
+                    message(1, ME, Some(2), 0),
+                ],
+            )],
+        );
+        assert_eq!(stats.correspondents, vec![(2, 2)]);
+        assert_eq!(stats.their_response_time, None);
+    }
+
+    #[test]
+    fn group() {
+        let stats = analyze(
+            ME,
+            &[
+                conversation(
+                    "1234",
+                    &[
+                        message(4, ME, None, 30),
+                        message(3, 3, None, 20),
+                        message(2, 3, None, 10),
+                        message(1, 2, None, 0),
+                    ],
+                ),
+                conversation("1-4", &[message(5, 4, Some(ME), 0)]),
+            ],
+        );
+        assert_eq!(stats.correspondents, vec![(3, 2), (2, 1), (4, 1)]);
+        // Group conversations don't count towards response times.
+        assert_eq!(stats.my_response_time, None);
+        assert!(stats.conversations[0].group);
+        assert_eq!(stats.conversations[0].others, vec![2, 3]);
+    }
+}
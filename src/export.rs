@@ -0,0 +1,296 @@
+//! Exporting the loaded data for use in other tools, like spreadsheets, pandas, or SQLite.
+
+use crate::{api, archive, score};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A row of `tweets.csv`.
+///
+/// The metrics are empty for tweets the Twitter API didn't return.
+#[derive(Debug, Serialize)]
+pub struct TweetRow<'a> {
+    pub id: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+    pub text: &'a str,
+    pub likes: Option<usize>,
+    pub retweets: Option<usize>,
+    pub replies: Option<usize>,
+    pub quotes: Option<usize>,
+    /// The tweet's score by the first metric in the config (see [`crate::score`]).
+    pub goodness: Option<f64>,
+    /// See [`archive::Kind::name`].
+    pub classification: &'static str,
+}
+
+impl<'a> TweetRow<'a> {
+    /// Build the row for `tweet`, with `goodness` scored by `metric`.
+    pub fn new(tweet: &Tweet<'a>, metric: &score::Metric) -> Self {
+        let m = tweet.metrics;
+        Self {
+            id: tweet.id,
+            created: tweet.created,
+            text: tweet.text,
+            likes: m.map(|m| m.likes),
+            retweets: m.map(|m| m.retweets),
+            replies: m.map(|m| m.replies),
+            quotes: m.map(|m| m.quotations),
+            goodness: m.map(|m| metric.score(m)),
+            classification: tweet.kind.name(),
+        }
+    }
+}
+
+/// A row of `followers.csv`.
+#[derive(Debug, Serialize)]
+pub struct FollowerRow<'a> {
+    pub username: &'a str,
+    pub followers: usize,
+    pub following: usize,
+    /// Followers per followed account. Empty for accounts that don't follow anyone.
+    pub ratio: Option<f64>,
+}
+
+impl<'a> From<&'a api::User> for FollowerRow<'a> {
+    fn from(user: &'a api::User) -> Self {
+        Self {
+            username: &user.username,
+            followers: user.metrics.followers,
+            following: user.metrics.following,
+            ratio: (user.metrics.following != 0)
+                .then(|| user.metrics.followers as f64 / user.metrics.following as f64),
+        }
+    }
+}
+
+/// Write the given rows to the CSV file at `path`, with a header row.
+///
+/// Returns the number of rows written, not counting the header.
+pub fn csv<T, I>(path: &Path, rows: I) -> anyhow::Result<usize>
+where
+    T: Serialize,
+    I: IntoIterator<Item = T>,
+{
+    let mut w =
+        ::csv::Writer::from_path(path).with_context(|| format!("create {}", path.display()))?;
+    let mut written = 0;
+    for row in rows {
+        w.serialize(row)
+            .with_context(|| format!("write row to {}", path.display()))?;
+        written += 1;
+    }
+    w.flush()
+        .with_context(|| format!("flush {}", path.display()))?;
+    Ok(written)
+}
+
+/// One of your tweets from the archive.
+#[derive(Debug)]
+pub struct Tweet<'a> {
+    pub id: u64,
+    pub created: time::OffsetDateTime,
+    pub text: &'a str,
+    pub kind: &'a archive::Kind,
+    /// `None` if the Twitter API didn't return the tweet, like if it's since been deleted.
+    pub metrics: Option<&'a api::PublicTweetMetrics>,
+}
+
+/// Everything that goes into a SQLite export.
+#[derive(Debug)]
+pub struct Dataset<'a> {
+    pub account: &'a archive::Account,
+    /// Every tweet in the archive.
+    pub tweets: Vec<Tweet<'a>>,
+    /// The metric the tweets' `goodness` is scored by.
+    pub metric: &'a score::Metric,
+    /// The tweets you retweeted.
+    pub retweets: &'a [archive::TweetRef],
+    pub likes: &'a [archive::LikeData],
+    /// Every follower and followed account the Twitter API told us about.
+    pub users: Vec<&'a api::User>,
+    pub follower_ids: &'a HashSet<u64>,
+    pub following_ids: &'a HashSet<u64>,
+}
+
+const SCHEMA: &str = r#"
+CREATE TABLE account (
+    id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    created TEXT NOT NULL
+);
+CREATE TABLE tweets (
+    id INTEGER PRIMARY KEY,
+    created TEXT NOT NULL,
+    text TEXT NOT NULL,
+    -- one of original, reply, self-reply, or quote
+    kind TEXT NOT NULL,
+    -- set for replies and self-replies
+    in_reply_to INTEGER,
+    -- set for quotes
+    quoted INTEGER,
+    quoted_author TEXT
+);
+CREATE INDEX tweets_created ON tweets (created);
+-- only for tweets the Twitter API returned, so not for deleted or protected tweets
+CREATE TABLE tweet_metrics (
+    tweet_id INTEGER PRIMARY KEY REFERENCES tweets (id),
+    likes INTEGER NOT NULL,
+    retweets INTEGER NOT NULL,
+    replies INTEGER NOT NULL,
+    quotes INTEGER NOT NULL,
+    -- the score by the first metric in the config
+    goodness REAL NOT NULL
+);
+CREATE TABLE retweets (
+    -- the id of the original tweet if known, and of the retweet otherwise
+    tweet_id INTEGER PRIMARY KEY,
+    author TEXT NOT NULL
+);
+CREATE TABLE likes (
+    tweet_id INTEGER PRIMARY KEY,
+    author TEXT,
+    text TEXT,
+    -- when the liked tweet was posted, if it's recent enough to tell
+    created TEXT,
+    url TEXT NOT NULL
+);
+CREATE INDEX likes_created ON likes (created);
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL,
+    followers INTEGER NOT NULL,
+    following INTEGER NOT NULL
+);
+CREATE INDEX users_username ON users (username);
+-- these reference users (id), but only if the API told us about the user
+CREATE TABLE followers (user_id INTEGER PRIMARY KEY);
+CREATE TABLE following (user_id INTEGER PRIMARY KEY);
+"#;
+
+/// Write the dataset to a new SQLite database at `path`, replacing any file that's there.
+///
+/// Timestamps are stored as RFC 3339 text, which SQLite's date functions understand, and
+/// which sorts correctly as long as all timestamps are in UTC (which Twitter's are).
+pub fn sqlite(path: &Path, data: &Dataset<'_>) -> anyhow::Result<()> {
+    use rusqlite::params;
+    use time::format_description::well_known::Rfc3339;
+
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("remove old {}", path.display())),
+    }
+    let mut db =
+        rusqlite::Connection::open(path).with_context(|| format!("create {}", path.display()))?;
+    let tx = db.transaction().context("start transaction")?;
+    tx.execute_batch(SCHEMA).context("create tables")?;
+
+    let ts = |t: time::OffsetDateTime| t.format(&Rfc3339).expect("timestamps are formattable");
+
+    tx.execute(
+        "INSERT INTO account (id, username, display_name, created) VALUES (?, ?, ?, ?)",
+        params![
+            data.account.id,
+            data.account.username,
+            data.account.display_name,
+            ts(data.account.created)
+        ],
+    )
+    .context("insert account")?;
+
+    {
+        let mut tweet = tx.prepare(
+            "INSERT INTO tweets (id, created, text, kind, in_reply_to, quoted, quoted_author) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        let mut metrics = tx.prepare(
+            "INSERT INTO tweet_metrics (tweet_id, likes, retweets, replies, quotes, goodness) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for t in &data.tweets {
+            let (in_reply_to, quoted) = match t.kind {
+                archive::Kind::Reply { to } | archive::Kind::SelfReply { to } => (Some(*to), None),
+                archive::Kind::Quote { of } => (None, Some(of)),
+                archive::Kind::Original | archive::Kind::Retweet { .. } => (None, None),
+            };
+            tweet
+                .execute(params![
+                    t.id,
+                    ts(t.created),
+                    t.text,
+                    t.kind.name(),
+                    in_reply_to,
+                    quoted.map(|q| q.id),
+                    quoted.map(|q| &q.author),
+                ])
+                .with_context(|| format!("insert tweet {}", t.id))?;
+            if let Some(m) = t.metrics {
+                metrics
+                    .execute(params![
+                        t.id,
+                        m.likes,
+                        m.retweets,
+                        m.replies,
+                        m.quotations,
+                        data.metric.score(m)
+                    ])
+                    .with_context(|| format!("insert metrics for tweet {}", t.id))?;
+            }
+        }
+
+        let mut retweet =
+            tx.prepare("INSERT OR IGNORE INTO retweets (tweet_id, author) VALUES (?, ?)")?;
+        for rt in data.retweets {
+            retweet
+                .execute(params![rt.id, rt.author])
+                .with_context(|| format!("insert retweet of {}", rt.id))?;
+        }
+
+        let mut like = tx.prepare(
+            "INSERT OR IGNORE INTO likes (tweet_id, author, text, created, url) \
+             VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for l in data.likes {
+            like.execute(params![
+                l.id,
+                l.author(),
+                l.text,
+                l.created().map(ts),
+                l.url
+            ])
+            .with_context(|| format!("insert like of {}", l.id))?;
+        }
+
+        let mut user = tx.prepare(
+            "INSERT OR IGNORE INTO users (id, username, followers, following) VALUES (?, ?, ?, ?)",
+        )?;
+        for u in &data.users {
+            user.execute(params![
+                u.id,
+                u.username,
+                u.metrics.followers,
+                u.metrics.following
+            ])
+            .with_context(|| format!("insert user {}", u.id))?;
+        }
+
+        let mut follower = tx.prepare("INSERT INTO followers (user_id) VALUES (?)")?;
+        for id in data.follower_ids {
+            follower
+                .execute(params![id])
+                .with_context(|| format!("insert follower {}", id))?;
+        }
+        let mut following = tx.prepare("INSERT INTO following (user_id) VALUES (?)")?;
+        for id in data.following_ids {
+            following
+                .execute(params![id])
+                .with_context(|| format!("insert followed account {}", id))?;
+        }
+    }
+
+    tx.commit().context("commit")?;
+    Ok(())
+}
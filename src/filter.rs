@@ -0,0 +1,279 @@
+//! Narrowing down which of your tweets are analyzed.
+//!
+//! Filters can be given on the command line or in the `[filter]` table of the config file (see
+//! [`crate::score`]), and apply to all your tweets except native retweets. Everything set must
+//! match for a tweet to be kept. Where a filter takes a list, like `hashtags`, matching any one
+//! entry is enough.
+//!
+//! ```toml
+//! [filter]
+//! exclude_replies = true
+//! media = "require"
+//! hashtags = ["rustlang"]
+//! lang = ["en"]
+//! text = ["(?i)async"]
+//! ```
+
+use crate::archive::{Kind, TweetData};
+use serde::{Deserialize, Deserializer};
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    /// Leave out replies to other people's tweets.
+    #[serde(default)]
+    pub exclude_replies: bool,
+    /// Leave out replies to your own tweets, so that each thread only counts once, by its first
+    /// tweet.
+    #[serde(default)]
+    pub thread_roots_only: bool,
+    /// Whether tweets must, or must not, have photos or videos attached.
+    #[serde(default)]
+    pub media: Option<MediaFilter>,
+    /// Hashtags (with or without the `#`) to keep tweets with. Case-insensitive.
+    #[serde(default)]
+    pub hashtags: Vec<String>,
+    /// Usernames (with or without the `@`) to keep tweets mentioning. Case-insensitive.
+    #[serde(default)]
+    pub mentions: Vec<String>,
+    /// Languages to keep tweets in, as the codes Twitter uses (like `en` or `de`).
+    #[serde(default)]
+    pub lang: Vec<String>,
+    /// Regular expressions to keep tweets whose text matches.
+    #[serde(default, deserialize_with = "regexes")]
+    pub text: Vec<regex::Regex>,
+    /// Regular expressions to leave out tweets whose text matches.
+    #[serde(default, deserialize_with = "regexes")]
+    pub exclude_text: Vec<regex::Regex>,
+}
+
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaFilter {
+    /// Only keep tweets with media.
+    Require,
+    /// Only keep tweets without media.
+    Exclude,
+}
+
+fn regexes<'de, D>(deserializer: D) -> Result<Vec<regex::Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|re| regex::Regex::new(re).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+impl Filter {
+    /// Whether this filters anything at all.
+    pub fn is_empty(&self) -> bool {
+        !self.exclude_replies
+            && !self.thread_roots_only
+            && self.media.is_none()
+            && self.hashtags.is_empty()
+            && self.mentions.is_empty()
+            && self.lang.is_empty()
+            && self.text.is_empty()
+            && self.exclude_text.is_empty()
+    }
+
+    /// Whether to keep the given tweet, which is of the given kind.
+    pub fn keep(&self, tweet: &TweetData, kind: &Kind) -> bool {
+        match kind {
+            Kind::Reply { .. } if self.exclude_replies => return false,
+            Kind::SelfReply { .. } if self.thread_roots_only => return false,
+            _ => {}
+        }
+        match self.media {
+            Some(MediaFilter::Require) if tweet.media().is_empty() => return false,
+            Some(MediaFilter::Exclude) if !tweet.media().is_empty() => return false,
+            _ => {}
+        }
+        if !self.hashtags.is_empty()
+            && !tweet.entities.hashtags.iter().any(|tag| {
+                self.hashtags
+                    .iter()
+                    .any(|want| want.trim_start_matches('#').eq_ignore_ascii_case(&tag.text))
+            })
+        {
+            return false;
+        }
+        if !self.mentions.is_empty()
+            && !tweet.entities.user_mentions.iter().any(|mention| {
+                self.mentions.iter().any(|want| {
+                    want.trim_start_matches('@')
+                        .eq_ignore_ascii_case(&mention.screen_name)
+                })
+            })
+        {
+            return false;
+        }
+        if !self.lang.is_empty()
+            && !self
+                .lang
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(&tweet.lang))
+        {
+            return false;
+        }
+        if !self.text.is_empty() || !self.exclude_text.is_empty() {
+            let text = tweet.display_text();
+            if !self.text.is_empty() && !self.text.iter().any(|re| re.is_match(&text)) {
+                return false;
+            }
+            if self.exclude_text.iter().any(|re| re.is_match(&text)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a tweet from the archive's JSON, filling in the fields every tweet has.
+    fn tweet(extra: &str) -> TweetData {
+        let json = format!(
+            r#"{{
+                "id": "1",
+                "created_at": "Wed Oct 10 20:19:24 +0000 2018",
+                "favorite_count": "0",
+                "retweet_count": "0",
+                "lang": "en",
+                {}
+            }}"#,
+            extra
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn plain() -> TweetData {
+        tweet(r#""full_text": "just a tweet""#)
+    }
+
+    fn filter(toml: &str) -> Filter {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn empty() {
+        let f = Filter::default();
+        assert!(f.is_empty());
+        assert!(f.keep(&plain(), &Kind::Original));
+        assert!(f.keep(&plain(), &Kind::Reply { to: 2 }));
+        assert!(f.keep(&plain(), &Kind::SelfReply { to: 2 }));
+    }
+
+    #[test]
+    fn replies() {
+        let f = filter("exclude_replies = true");
+        assert!(!f.is_empty());
+        assert!(f.keep(&plain(), &Kind::Original));
+        assert!(!f.keep(&plain(), &Kind::Reply { to: 2 }));
+        assert!(f.keep(&plain(), &Kind::SelfReply { to: 2 }));
+
+        let f = filter("thread_roots_only = true");
+        assert!(f.keep(&plain(), &Kind::Reply { to: 2 }));
+        assert!(!f.keep(&plain(), &Kind::SelfReply { to: 2 }));
+    }
+
+    #[test]
+    fn media() {
+        let with = tweet(
+            r#""full_text": "look https://t.co/x",
+               "entities": {
+                 "media": [
+                   { "id": "2", "type": "photo", "url": "https://t.co/x",
+                     "media_url_https": "https://pbs.twimg.com/media/x.jpg",
+                     "expanded_url": "https://twitter.com/a/status/1/photo/1",
+                     "display_url": "pic.twitter.com/x" }
+                 ]
+               }"#,
+        );
+        let f = filter(r#"media = "require""#);
+        assert!(f.keep(&with, &Kind::Original));
+        assert!(!f.keep(&plain(), &Kind::Original));
+        let f = filter(r#"media = "exclude""#);
+        assert!(!f.keep(&with, &Kind::Original));
+        assert!(f.keep(&plain(), &Kind::Original));
+    }
+
+    #[test]
+    fn hashtags_and_mentions() {
+        let t = tweet(
+            r##""full_text": "#RustLang is neat, says @jonhoo",
+                "entities": {
+                  "hashtags": [ { "text": "RustLang" } ],
+                  "user_mentions": [
+                    { "id": "2", "screen_name": "jonhoo", "name": "Jon", "indices": ["24", "31"] }
+                  ]
+                }"##,
+        );
+        assert!(filter(r##"hashtags = ["#rustlang"]"##).keep(&t, &Kind::Original));
+        assert!(filter(r#"hashtags = ["RUSTLANG", "go"]"#).keep(&t, &Kind::Original));
+        assert!(!filter(r#"hashtags = ["go"]"#).keep(&t, &Kind::Original));
+        assert!(!filter(r#"hashtags = ["rustlang"]"#).keep(&plain(), &Kind::Original));
+
+        assert!(filter(r#"mentions = ["@JonHoo"]"#).keep(&t, &Kind::Original));
+        assert!(filter(r#"mentions = ["jonhoo"]"#).keep(&t, &Kind::Original));
+        assert!(!filter(r#"mentions = ["@rustlang"]"#).keep(&t, &Kind::Original));
+    }
+
+    #[test]
+    fn lang() {
+        assert!(filter(r#"lang = ["de", "EN"]"#).keep(&plain(), &Kind::Original));
+        assert!(!filter(r#"lang = ["de"]"#).keep(&plain(), &Kind::Original));
+    }
+
+    #[test]
+    fn text() {
+        let t = tweet(
+            r#""full_text": "Async &amp; await https://t.co/y",
+               "entities": {
+                 "urls": [
+                   { "url": "https://t.co/y", "expanded_url": "https://example.com/post",
+                     "display_url": "example.com/post" }
+                 ]
+               }"#,
+        );
+        // Matching is done on the text as shown, with links expanded and entities un-escaped.
+        assert!(filter(r#"text = ["example\\.com"]"#).keep(&t, &Kind::Original));
+        assert!(filter(r#"text = ["Async & await"]"#).keep(&t, &Kind::Original));
+        assert!(!filter(r#"text = ["t\\.co"]"#).keep(&t, &Kind::Original));
+        assert!(filter(r#"text = ["nope", "(?i)ASYNC"]"#).keep(&t, &Kind::Original));
+
+        assert!(!filter(r#"exclude_text = ["await"]"#).keep(&t, &Kind::Original));
+        assert!(filter(r#"exclude_text = ["await"]"#).keep(&plain(), &Kind::Original));
+        // Exclusions win over inclusions.
+        let f = filter(
+            r#"
+            text = ["Async"]
+            exclude_text = ["await"]
+            "#,
+        );
+        assert!(!f.keep(&t, &Kind::Original));
+    }
+
+    #[test]
+    fn everything_must_match() {
+        let f = filter(
+            r#"
+            exclude_replies = true
+            lang = ["en"]
+            text = ["tweet"]
+            "#,
+        );
+        assert!(f.keep(&plain(), &Kind::Original));
+        assert!(!f.keep(&plain(), &Kind::Reply { to: 2 }));
+        assert!(!f.keep(&tweet(r#""full_text": "hi""#), &Kind::Original));
+    }
+
+    #[test]
+    fn bad_regex() {
+        assert!(toml::from_str::<Filter>(r#"text = ["("]"#).is_err());
+    }
+}
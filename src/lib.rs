@@ -1,2 +1,9 @@
 pub mod api;
 pub mod archive;
+pub mod dms;
+pub mod export;
+pub mod filter;
+pub mod notable;
+pub mod report;
+pub mod score;
+pub mod threads;
@@ -1,11 +1,11 @@
 use anyhow::Context;
 use clap::Parser;
 use oauth2::ClientId;
-use ornithology_cli::{api, archive};
+use ornithology_cli::{api, archive, dms, export, filter, notable, report, score, threads};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -32,13 +32,302 @@ struct Args {
     #[clap(long)]
     fresh: bool,
 
-    /// Path to your Twitter archive .zip file.
+    /// Compute all statistics from the archive alone, without talking to the Twitter API.
+    ///
+    /// The archive only records the number of likes and retweets each tweet had at the time the
+    /// archive was generated, so reply and quote counts are all zero in this mode. Followers also
+    /// cannot be looked up, so follower statistics are left empty.
+    ///
+    /// This mode never reads or writes the cache.
+    #[clap(long)]
+    offline: bool,
+
+    /// Also analyze your direct messages.
+    ///
+    /// Since DMs are private, this is off by default. When enabled, DM statistics are printed, and
+    /// a searchable transcript of all your conversations is written to `ornithology-dms.html`. That
+    /// page is entirely self-contained, and nothing from your DMs is ever sent to the Twitter API.
+    #[clap(long)]
+    dms: bool,
+
+    /// How to show tweets in the generated page.
+    ///
+    /// `embed` uses Twitter's own embedded tweets, which look nice and show up-to-date metrics,
+    /// but need network access and let Twitter know whenever you look at the page. `static` builds
+    /// the tweets from the archive instead, which works for deleted tweets and without network
+    /// access, and doesn't load anything from Twitter at all.
+    #[clap(long, arg_enum, default_value = "embed")]
+    renderer: report::Renderer,
+
+    /// How to include the photos and videos you attached to tweets in the generated page.
+    ///
+    /// The archive has a copy of all your media, which is shown for tweets that cannot be
+    /// embedded, like tweets that have since been deleted, or if Twitter's embedding script is
+    /// blocked. `copy` copies the files into `ornithology-media/` next to the page, `embed`
+    /// includes them in the page itself (which makes it larger, but self-contained), and `none`
+    /// leaves them out.
+    ///
+    /// Defaults to `embed` with the static renderer so that the page is a single file, and `copy`
+    /// otherwise.
+    #[clap(long, arg_enum)]
+    media: Option<MediaMode>,
+
+    /// What kind of report to generate.
+    ///
+    /// `html` is a page that is opened in your browser once generated. `markdown` is a document
+    /// with the same lists, for pasting into blog posts and notes. `json` is a document with
+    /// every computed list, including the full tweet and user records and the scores they were
+    /// ranked by, for further processing by other tools. Its format is documented in the
+    /// `report::json` module.
+    #[clap(long, arg_enum, default_value = "html")]
+    format: Format,
+
+    /// Where to write the report.
+    ///
+    /// Defaults to `ornithology.html`, `ornithology.md`, or `ornithology.json` in the current
+    /// directory, depending on `--format`.
+    #[clap(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    /// Directory with templates to use for the generated page instead of the built-in ones.
+    ///
+    /// Only the templates you want to change need to be in there; anything missing is taken from
+    /// the built-in theme. See the `report` module documentation for the available templates and
+    /// the variables they're rendered with.
+    #[clap(long)]
+    template: Option<PathBuf>,
+
+    /// Only report on your tweets from this day (YYYY-MM-DD) on.
+    ///
+    /// Tweets from before are still used to decide which of the later ones were notable at the
+    /// time.
+    #[clap(long, value_parser = parse_date)]
+    since: Option<time::Date>,
+
+    /// Only report on your tweets up until and including this day (YYYY-MM-DD).
+    #[clap(long, value_parser = parse_date)]
+    until: Option<time::Date>,
+
+    /// Generate a separate report for each year, quarter, or month.
+    ///
+    /// Each report is written next to where the report would otherwise go, with the period added
+    /// to its name, like `ornithology-2021.html` or `ornithology-2021-Q3.html`. The tweet lists
+    /// only include tweets from that period, whereas the lists of accounts and likes are the same
+    /// in every report, since the archive doesn't say when you followed or liked something.
+    #[clap(long, arg_enum)]
+    per: Option<Per>,
+
+    /// Leave out your replies to other people's tweets.
+    #[clap(long)]
+    exclude_replies: bool,
+
+    /// Leave out your replies to your own tweets, so that threads only count by their first tweet.
+    #[clap(long)]
+    thread_roots_only: bool,
+
+    /// Only analyze tweets with (`require`) or without (`exclude`) photos or videos.
+    #[clap(long, arg_enum, value_name = "WHETHER")]
+    with_media: Option<filter::MediaFilter>,
+
+    /// Only analyze tweets with this hashtag. Can be given more than once to allow any of several.
+    #[clap(long, value_name = "TAG")]
+    hashtag: Vec<String>,
+
+    /// Only analyze tweets mentioning this user. Can be given more than once to allow any of
+    /// several.
+    #[clap(long, value_name = "USERNAME")]
+    mention: Vec<String>,
+
+    /// Only analyze tweets Twitter thinks are in this language, like `en`. Can be given more than
+    /// once to allow any of several.
+    #[clap(long)]
+    lang: Vec<String>,
+
+    /// Only analyze tweets whose text matches this regular expression. Can be given more than
+    /// once to allow any of several.
+    #[clap(long, value_name = "REGEX", value_parser = regex::Regex::new)]
+    text: Vec<regex::Regex>,
+
+    /// Leave out tweets whose text matches this regular expression.
+    #[clap(long, value_name = "REGEX", value_parser = regex::Regex::new)]
+    exclude_text: Vec<regex::Regex>,
+
+    /// TOML file with the metrics to rank tweets by.
+    ///
+    /// Each metric is a weighted sum of likes, retweets, replies, and quotes, and gets its own
+    /// "top" and "notable" list in the report. See the `score` module documentation for the
+    /// format. Defaults to metrics for overall engagement, replies and quotes, and shares.
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Path to your Twitter archive .zip file, or the directory you extracted it to.
+    ///
+    /// If you have extracted the archive, you can also point this at just its `data/` directory.
     ///
     /// To get this file, follow the instructions at
     /// <https://help.twitter.com/en/managing-your-account/how-to-download-your-twitter-archive>.
     /// It takes about 24h to get the archive after you submit the request, so come back later if
     /// you don't yet have said file :)
     archive: PathBuf,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Export the loaded data for use in other tools instead of generating a report.
+    #[clap(subcommand)]
+    Export(Export),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Export {
+    /// Write all your tweets to `tweets.csv`, and your followers to `followers.csv`.
+    Csv {
+        /// The directory to write the files to.
+        #[clap(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Write everything into a SQLite database.
+    ///
+    /// The database has tables for your account, tweets, tweet metrics, retweets, likes,
+    /// followers, followed accounts, and the metrics of the latter two. Any existing database at
+    /// the given path is replaced.
+    Sqlite {
+        /// The database file to write to.
+        #[clap(long, default_value = "ornithology.db")]
+        file: PathBuf,
+    },
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Html,
+    Json,
+    Markdown,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MediaMode {
+    Copy,
+    Embed,
+    None,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Per {
+    Year,
+    Quarter,
+    Month,
+}
+
+impl Per {
+    /// The first day of the period `date` is in.
+    fn start(self, date: time::Date) -> time::Date {
+        let month = match self {
+            Per::Year => time::Month::January,
+            Per::Quarter => time::Month::try_from((date.month() as u8 - 1) / 3 * 3 + 1)
+                .expect("quarters start in valid months"),
+            Per::Month => date.month(),
+        };
+        time::Date::from_calendar_date(date.year(), month, 1).expect("every month has a 1st")
+    }
+
+    /// The first day of the period after the one that starts on `start`.
+    fn next(self, start: time::Date) -> time::Date {
+        let months = match self {
+            Per::Year => 12,
+            Per::Quarter => 3,
+            Per::Month => 1,
+        };
+        let month = start.month() as i32 - 1 + months;
+        time::Date::from_calendar_date(
+            start.year() + month / 12,
+            time::Month::try_from((month % 12 + 1) as u8).expect("month is in 1..=12"),
+            1,
+        )
+        .expect("every month has a 1st")
+    }
+
+    fn label(self, start: time::Date) -> String {
+        match self {
+            Per::Year => start.year().to_string(),
+            Per::Quarter => format!("{}-Q{}", start.year(), (start.month() as u8 - 1) / 3 + 1),
+            Per::Month => format!("{}-{:02}", start.year(), start.month() as u8),
+        }
+    }
+}
+
+/// A stretch of time to generate a report for.
+#[derive(Debug)]
+struct Period {
+    /// How to refer to the period, unless it's all of time.
+    label: Option<String>,
+    /// The first day of the period, if it has one.
+    since: Option<time::Date>,
+    /// The last day of the period, if it has one.
+    until: Option<time::Date>,
+}
+
+impl Period {
+    fn contains(&self, date: time::Date) -> bool {
+        self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date <= until)
+    }
+}
+
+/// Split the time between `since` and `until` into the periods to generate reports for.
+///
+/// With `per`, there's one period for each year/quarter/month that has tweets in it, as there's
+/// not much point in a report without any.
+fn periods(
+    since: Option<time::Date>,
+    until: Option<time::Date>,
+    per: Option<Per>,
+    tweets: &[api::Tweet],
+) -> Vec<Period> {
+    let all = Period {
+        label: match (since, until) {
+            (None, None) => None,
+            (Some(since), None) => Some(format!("since {}", since)),
+            (None, Some(until)) => Some(format!("until {}", until)),
+            (Some(since), Some(until)) => Some(format!("{} to {}", since, until)),
+        },
+        since,
+        until,
+    };
+    let per = match per {
+        Some(per) => per,
+        None => return vec![all],
+    };
+
+    let mut starts: Vec<time::Date> = tweets
+        .iter()
+        .map(|t| t.created.date())
+        .filter(|&d| all.contains(d))
+        .map(|d| per.start(d))
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts
+        .into_iter()
+        .map(|start| {
+            let last = per
+                .next(start)
+                .previous_day()
+                .expect("not the first day ever");
+            Period {
+                label: Some(per.label(start)),
+                since: Some(since.map_or(start, |since| since.max(start))),
+                until: Some(until.map_or(last, |until| until.min(last))),
+            }
+        })
+        .collect()
+}
+
+fn parse_date(s: &str) -> anyhow::Result<time::Date> {
+    let format = time::macros::format_description!("[year]-[month]-[day]");
+    time::Date::parse(s, &format).with_context(|| format!("{} is not a YYYY-MM-DD date", s))
 }
 
 #[tokio::main]
@@ -47,161 +336,215 @@ async fn main() -> anyhow::Result<()> {
     let toptn = args.top_tweets as usize;
     let topfn = args.top_followers as usize;
     let archive = &*Box::leak(args.archive.into_boxed_path());
+    let config = match &args.config {
+        Some(path) => score::Config::load(path).context("load config")?,
+        None => score::Config::default(),
+    };
+
+    // Filters on the command line add to the ones in the config.
+    let mut filter = config.filter.clone();
+    filter.exclude_replies |= args.exclude_replies;
+    filter.thread_roots_only |= args.thread_roots_only;
+    if args.with_media.is_some() {
+        filter.media = args.with_media;
+    }
+    filter.hashtags.extend(args.hashtag.iter().cloned());
+    filter.mentions.extend(args.mention.iter().cloned());
+    filter.lang.extend(args.lang.iter().cloned());
+    filter.text.extend(args.text.iter().cloned());
+    filter
+        .exclude_text
+        .extend(args.exclude_text.iter().cloned());
 
     let Loaded {
-        me,
-        old_rt_ids,
+        account,
+        old_rts,
         mut tweets,
+        archived,
         mut followers,
-    } = load(!args.fresh, archive).await.context("load dataset")?;
-
-    let mut lists_of_tweets: HashMap<&'static str, Vec<String>> = HashMap::new();
-
-    // It's fun to surface RTs that people may have forgotten about.
-    if !old_rt_ids.is_empty() {
-        // XXX: can theoretically look up tweet originals using
-        // expansions=referenced_tweets.id and look for type=retweeted
-        println!("remember these old retweets:");
-        let mut rng = rand::thread_rng();
-        let entry = lists_of_tweets
-            .entry("old_rts")
-            .or_insert_with(|| Vec::with_capacity(toptn));
-        for old_rt_id in old_rt_ids.choose_multiple(&mut rng, toptn) {
-            println!("https://twitter.com/{}/status/{}", me, old_rt_id);
-            entry.push(old_rt_id.to_string());
-        }
-    }
+        following,
+        follower_ids,
+        following_ids,
+        likes,
+        conversations,
+    } = load(!args.fresh, args.offline, args.dms, filter, archive)
+        .await
+        .context("load dataset")?;
+    let me = &account.username;
 
-    // Metrics that depend on the time-based state.
-    // For example, how good was this tweet relative to other tweets at/up until that point in
-    // time. The idea being that what makes a tweet "notable" isn't "does it have a lot of likes",
-    // but "did it get a lot of likes relative to other tweets you twote back then"?
-    {
-        // For these, we need the tweet list to be sorted by time, so we use a block that sorts and
-        // then borrows as read-only:
-        tweets.sort_unstable_by_key(|t| t.created);
-        let tweets = &tweets[..];
-        fn find_notable(
-            tweets: &[api::Tweet],
-            mut metric: impl FnMut(&api::Tweet) -> usize,
-            at_least: f64,
-            at_least_x: f64,
-        ) -> Vec<(f64, f64, usize)> {
-            let mut avg = 0.0f64;
-            let mut notable = Vec::new();
-            for (i, t) in tweets.iter().enumerate() {
-                let floor = (at_least_x * avg).max(at_least);
-                let g = (metric)(t) as f64;
-                if g > floor {
-                    notable.push((g / avg, avg, i));
+    if let Some(Command::Export(export)) = &args.command {
+        match export {
+            Export::Csv { dir } => {
+                let f = dir.join("tweets.csv");
+                let all = export_tweets(&archived, &tweets);
+                let written = export::csv(
+                    &f,
+                    all.iter()
+                        .map(|t| export::TweetRow::new(t, &config.metrics[0])),
+                )?;
+                eprintln!("wrote {} tweets to {}", written, f.display());
+                let unhydrated = all.iter().filter(|t| t.metrics.is_none()).count();
+                if unhydrated != 0 {
+                    eprintln!(
+                        "{} tweets have no metrics since the Twitter API didn't return them",
+                        unhydrated
+                    );
+                }
+                let f = dir.join("followers.csv");
+                let written = export::csv(&f, followers.iter().map(export::FollowerRow::from))?;
+                eprintln!("wrote {} followers to {}", written, f.display());
+            }
+            Export::Sqlite { file } => {
+                let dataset = export::Dataset {
+                    account: &account,
+                    tweets: export_tweets(&archived, &tweets),
+                    metric: &config.metrics[0],
+                    retweets: &old_rts.iter().map(|rt| rt.link(me)).collect::<Vec<_>>(),
+                    likes: &likes,
+                    users: followers.iter().chain(&following).collect(),
+                    follower_ids: &follower_ids,
+                    following_ids: &following_ids,
+                };
+                export::sqlite(file, &dataset)?;
+                eprintln!("wrote everything to {}", file.display());
+                let unhydrated = dataset
+                    .tweets
+                    .iter()
+                    .filter(|t| t.metrics.is_none())
+                    .count();
+                if unhydrated != 0 {
+                    eprintln!(
+                        "{} tweets have no metrics since the Twitter API didn't return them",
+                        unhydrated
+                    );
                 }
-                avg = 0.5 * g + 0.5 * avg;
             }
-            notable.sort_unstable_by_key(|(x, _, i)| {
-                // Reverse because we want the top ones to come first, not last.
-                std::cmp::Reverse(((1000.0 * x).round() as usize, (metric)(&tweets[*i])))
-            });
-            notable
         }
+        return Ok(());
+    }
 
-        println!("notable tweets:");
-        let notable = find_notable(&tweets, |t| t.goodness(), 10.0, 2.0);
-        let entry = lists_of_tweets
-            .entry("notable_tweets")
-            .or_insert_with(|| Vec::with_capacity(toptn));
-        for (_, avg, i) in notable.into_iter().take(toptn) {
-            let tweet = &tweets[i];
-            println!(
-                "https://twitter.com/{}/status/{} ({} likes/{} rts when avg was {:.2})",
-                me, tweet.id, tweet.metrics.likes, tweet.metrics.retweets, avg
-            );
-            entry.push(tweet.id.to_string());
-        }
+    // Threads are chains of self-replies, which only the archive knows about.
+    let threads = threads::reconstruct(&tweets, |id| match archived.get(&id)?.kind {
+        archive::Kind::SelfReply { to } => Some(to),
+        _ => None,
+    });
+    let thread_of: HashMap<u64, &threads::Thread> = threads.iter().map(|t| (t.root, t)).collect();
 
-        println!("talked-about tweets:");
-        let entry = lists_of_tweets
-            .entry("talked_about_tweets")
-            .or_insert_with(|| Vec::with_capacity(toptn));
-        let notable = find_notable(
-            &tweets,
-            |t| 2 * t.metrics.quotations + t.metrics.replies,
-            10.0,
-            2.0,
-        );
-        for (_, avg, i) in notable.into_iter().take(toptn) {
-            let tweet = &tweets[i];
-            println!(
-                "https://twitter.com/{}/status/{} ({} quotes + {} replies when avg as {:.2})",
-                me, tweet.id, tweet.metrics.quotations, tweet.metrics.replies, avg
-            );
-            entry.push(tweet.id.to_string());
-        }
+    // Everything we know locally about one of your own tweets.
+    let own_tweet = |tweet: &api::Tweet| report::TweetEntry {
+        thread: thread_of.get(&tweet.id).map(|t| report::ThreadSummary {
+            length: t.tweets.len(),
+            metrics: t.metrics.clone(),
+        }),
+        ..report::TweetEntry::new(
+            tweet.id,
+            report::Card {
+                author: Some(me.to_string()),
+                text: archived.get(&tweet.id).map(|a| a.text.clone()),
+                created: Some(tweet.created),
+                metrics: Some(tweet.metrics.clone()),
+                ..Default::default()
+            },
+        )
+    };
 
-        println!("over-shared tweets:");
-        let entry = lists_of_tweets
-            .entry("over_shared_tweets")
-            .or_insert_with(|| Vec::with_capacity(toptn));
-        let notable = find_notable(
-            &tweets,
-            |t| 2 * t.metrics.quotations + t.metrics.retweets,
-            10.0,
-            2.0,
-        );
-        for (_, avg, i) in notable.into_iter().take(toptn) {
-            let tweet = &tweets[i];
-            println!(
-                "https://twitter.com/{}/status/{} ({} quotes + {} retweets when avg as {:.2})",
-                me, tweet.id, tweet.metrics.quotations, tweet.metrics.retweets, avg
-            );
-            entry.push(tweet.id.to_string());
+    let mut lists: HashMap<String, Vec<report::Entry>> = HashMap::new();
+
+    // It's fun to surface RTs that people may have forgotten about.
+    let mut rng = rand::thread_rng();
+    let entry = lists
+        .entry("old_rts".to_string())
+        .or_insert_with(|| Vec::with_capacity(toptn));
+    if !old_rts.is_empty() {
+        println!("remember these old retweets:");
+        for old_rt in old_rts.choose_multiple(&mut rng, toptn) {
+            let link = old_rt.link(me);
+            println!("{}", link.url());
+            entry.push(report::Entry::Tweet(report::TweetEntry::new(
+                link.id,
+                report::Card {
+                    url: link.url(),
+                    author: Some(old_rt.of.author.clone()),
+                    text: Some(old_rt.text.clone()),
+                    // When the original was posted, if we know which tweet it is, and when you
+                    // retweeted it otherwise.
+                    created: old_rt
+                        .of
+                        .id
+                        .and_then(archive::snowflake_time)
+                        .or(Some(old_rt.created)),
+                    ..Default::default()
+                },
+            )));
         }
     }
 
-    // Now to the boring "best/most of all time" bits:
-    println!("top tweets:");
-    let entry = lists_of_tweets
-        .entry("top_tweets")
+    // Same goes for likes. The archive doesn't record when you liked something though, so we
+    // have to go by when the liked tweet was posted instead.
+    let entry = lists
+        .entry("old_likes".to_string())
         .or_insert_with(|| Vec::with_capacity(toptn));
-    tweets.sort_unstable_by_key(|t| t.goodness());
-    for tweet in tweets.iter().rev().take(toptn) {
-        println!(
-            "https://twitter.com/{}/status/{} ({} likes/{} rts)",
-            me, tweet.id, tweet.metrics.likes, tweet.metrics.retweets
-        );
-        entry.push(tweet.id.to_string());
+    let a_year_ago = time::OffsetDateTime::now_utc() - time::Duration::days(365);
+    let old_likes: Vec<_> = likes
+        .iter()
+        // Tweets from before snowflake ids don't have a time, but they're certainly old.
+        .filter(|like| like.created().is_none_or(|t| t < a_year_ago))
+        .collect();
+    if !old_likes.is_empty() {
+        println!("remember these old likes:");
+        for like in old_likes.choose_multiple(&mut rng, toptn) {
+            println!("{}", like.url);
+            entry.push(report::Entry::Tweet(report::TweetEntry::new(
+                like.id,
+                report::Card {
+                    author: like.author(),
+                    text: like.text.clone(),
+                    created: like.created(),
+                    ..Default::default()
+                },
+            )));
+        }
     }
 
-    println!("most talked-about tweets:");
-    let entry = lists_of_tweets
-        .entry("most_talked_about_tweets")
-        .or_insert_with(|| Vec::with_capacity(toptn));
-    tweets.sort_unstable_by_key(|t| 2 * t.metrics.quotations + t.metrics.replies);
-    for tweet in tweets.iter().rev().take(toptn) {
-        println!(
-            "https://twitter.com/{}/status/{} ({} quotes/{} replies)",
-            me, tweet.id, tweet.metrics.quotations, tweet.metrics.replies
-        );
-        entry.push(tweet.id.to_string());
+    // Who do you like the most (or, well, like the tweets of)?
+    println!("most liked authors:");
+    let entry = lists
+        .entry("most_liked_authors".to_string())
+        .or_insert_with(|| Vec::with_capacity(topfn));
+    let mut liked_authors: HashMap<String, usize> = HashMap::new();
+    for author in likes.iter().filter_map(|like| like.author()) {
+        *liked_authors.entry(author).or_default() += 1;
+    }
+    let mut liked_authors: Vec<_> = liked_authors.into_iter().collect();
+    liked_authors.sort_unstable_by(|(a1, n1), (a2, n2)| n2.cmp(n1).then_with(|| a1.cmp(a2)));
+    for (author, n) in liked_authors.into_iter().take(topfn) {
+        println!("https://twitter.com/{} ({} likes)", author, n);
+        let user = followers
+            .iter()
+            .chain(&following)
+            .find(|u| u.username.eq_ignore_ascii_case(&author))
+            .cloned();
+        entry.push(report::Entry::User(report::UserEntry {
+            score: Some(n as f64),
+            ..report::UserEntry::new(author, user)
+        }));
     }
 
-    println!("most shared tweets:");
-    let entry = lists_of_tweets
-        .entry("most_shared_tweets")
-        .or_insert_with(|| Vec::with_capacity(toptn));
-    tweets.sort_unstable_by_key(|t| 2 * t.metrics.quotations + t.metrics.retweets);
-    for tweet in tweets.iter().rev().take(toptn) {
-        println!(
-            "https://twitter.com/{}/status/{} ({} quotes/{} rts)",
-            me, tweet.id, tweet.metrics.quotations, tweet.metrics.retweets
-        );
-        entry.push(tweet.id.to_string());
+    // And how has your liking changed over the years?
+    println!("likes by year of the liked tweet:");
+    let mut like_volume: BTreeMap<i32, usize> = BTreeMap::new();
+    for created in likes.iter().filter_map(|like| like.created()) {
+        *like_volume.entry(created.year()).or_default() += 1;
+    }
+    for (year, n) in &like_volume {
+        println!("{}: {} likes", year, n);
     }
 
     // Then we move on to follower stats.
     // First the obvious one:
     println!("top followers:");
-    let entry = lists_of_tweets
-        .entry("top_followers")
+    let entry = lists
+        .entry("top_followers".to_string())
         .or_insert_with(|| Vec::with_capacity(topfn));
     followers.sort_unstable_by_key(|f| f.metrics.followers);
     for follower in followers.iter().rev().take(topfn) {
@@ -209,7 +552,10 @@ async fn main() -> anyhow::Result<()> {
             "https://twitter.com/{} ({} followers)",
             follower.username, follower.metrics.followers
         );
-        entry.push(follower.username.to_string());
+        entry.push(report::Entry::User(report::UserEntry {
+            score: Some(follower.metrics.followers as f64),
+            ..report::UserEntry::new(follower.username.clone(), Some(follower.clone()))
+        }));
     }
 
     // What is a "neat" follower?
@@ -218,188 +564,968 @@ async fn main() -> anyhow::Result<()> {
     // other accounts, it's not that interesting (they probably just follow everyone back). But if
     // they follow _just_ me, that's very neat.
     println!("neat followers:");
-    let entry = lists_of_tweets
-        .entry("neat_followers")
+    let entry = lists
+        .entry("neat_followers".to_string())
         .or_insert_with(|| Vec::with_capacity(topfn));
-    followers
-        .sort_unstable_by_key(|f| f.metrics.followers as isize - 10 * f.metrics.following as isize);
+    followers.sort_unstable_by_key(|f| f.neatness());
     for follower in followers.iter().rev().take(topfn) {
         println!(
             "https://twitter.com/{} ({} followers but only following {})",
             follower.username, follower.metrics.followers, follower.metrics.following
         );
-        entry.push(follower.username.to_string());
+        entry.push(report::Entry::User(report::UserEntry {
+            score: Some(follower.neatness() as f64),
+            ..report::UserEntry::new(follower.username.clone(), Some(follower.clone()))
+        }));
     }
 
-    let groups = Vec::from([
-        ("top_tweets", "Top tweets"),
-        ("most_talked_about_tweets", "Most talked about tweets"),
-        ("most_shared_tweets", "Most shared tweets"),
-        ("notable_tweets", "Notable tweets (at the time)"),
-        ("talked_about_tweets", "Talked about tweets (at the time)"),
-        ("over_shared_tweets", "Widely shared tweets (at the time)"),
-        ("old_rts", "Random old retweets"),
+    // Who follows you is only half the story though -- it's also interesting to compare it to who
+    // _you_ follow. We use the archive's ids for this rather than the users we got from the API,
+    // since the API won't tell us about suspended or deactivated accounts.
+    let mutuals: HashSet<u64> = follower_ids.intersection(&following_ids).copied().collect();
+    let not_following_back: HashSet<u64> =
+        following_ids.difference(&follower_ids).copied().collect();
+    let fans: HashSet<u64> = follower_ids.difference(&following_ids).copied().collect();
+    println!(
+        "{} mutuals, {} you follow who don't follow you back, and {} followers you don't follow",
+        mutuals.len(),
+        not_following_back.len(),
+        fans.len()
+    );
+    let users: HashMap<u64, &api::User> = followers
+        .iter()
+        .chain(&following)
+        .map(|u| (u.id, u))
+        .collect();
+    for (id, title, set) in [
+        ("neat_mutuals", "neat mutuals", &mutuals),
+        (
+            "neat_not_following_back",
+            "neat accounts that don't follow you back",
+            &not_following_back,
+        ),
+        ("neat_fans", "neat followers you don't follow", &fans),
+    ] {
+        println!("{}:", title);
+        let entry = lists
+            .entry(id.to_string())
+            .or_insert_with(|| Vec::with_capacity(topfn));
+        let mut set: Vec<&api::User> = set.iter().filter_map(|id| users.get(id)).copied().collect();
+        set.sort_unstable_by_key(|u| u.neatness());
+        for user in set.iter().rev().take(topfn) {
+            println!(
+                "https://twitter.com/{} ({} followers, following {})",
+                user.username, user.metrics.followers, user.metrics.following
+            );
+            entry.push(report::Entry::User(report::UserEntry {
+                score: Some(user.neatness() as f64),
+                ..report::UserEntry::new(user.username.clone(), Some((*user).clone()))
+            }));
+        }
+    }
+
+    let tweet_groups: Vec<report::Group> = config
+        .metrics
+        .iter()
+        .map(|m| report::Group::new(&format!("top_{}", m.name), &m.top_title()))
+        .chain(
+            config
+                .metrics
+                .iter()
+                .map(|m| report::Group::new(&format!("notable_{}", m.name), &m.notable_title())),
+        )
+        .chain([
+            report::Group::new("top_threads", "Top threads"),
+            report::Group::new("notable_threads", "Notable threads (at the time)"),
+            report::Group::new("old_rts", "Random old retweets"),
+            report::Group::new("old_likes", "Random old likes"),
+        ])
+        .collect();
+    let user_groups = Vec::from([
+        report::Group::new("top_followers", "Top followers"),
+        report::Group::new("neat_followers", "Neat followers"),
+        report::Group::new("neat_mutuals", "Neat mutuals"),
+        report::Group::new(
+            "neat_not_following_back",
+            "Neat accounts that don't follow you back",
+        ),
+        report::Group::new("neat_fans", "Neat followers you don't follow"),
+        report::Group::new("most_liked_authors", "Most liked authors"),
     ]);
-    for (id, _) in &groups {
-        assert!(lists_of_tweets.contains_key(id), "{}", id);
+    if args.dms {
+        // DMs only ever refer to users by id, so we use whatever usernames we already happen to
+        // know from followers and followed accounts rather than looking up any more.
+        let name = |id: u64| match users.get(&id) {
+            Some(user) => format!("@{}", user.username),
+            None if id == account.id => format!("@{}", me),
+            None => format!("user {}", id),
+        };
+
+        let stats = dms::analyze(account.id, &conversations);
+        println!("top dm correspondents:");
+        for (id, n) in stats.correspondents.iter().take(topfn) {
+            println!("{} ({} messages)", name(*id), n);
+        }
+        println!("longest dm conversations:");
+        for c in stats.conversations.iter().take(topfn) {
+            let others: Vec<_> = c.others.iter().map(|&id| name(id)).collect();
+            println!(
+                "with {} ({} messages from {} to {})",
+                others.join(", "),
+                c.messages,
+                c.first.date(),
+                c.last.date()
+            );
+        }
+        if let Some(t) = stats.my_response_time {
+            println!("you usually respond to dms within {}", humanize(t));
+        }
+        if let Some(t) = stats.their_response_time {
+            println!("others usually respond to your dms within {}", humanize(t));
+        }
+        println!("busiest dm months:");
+        for (month, n) in stats.busiest_months.iter().take(toptn) {
+            println!(
+                "{}-{:02}: {} messages",
+                month.year(),
+                month.month() as u8,
+                n
+            );
+        }
+
+        let context = dm_context(me, &stats, &conversations, name);
+        let html = report::render("dms.html", &context, args.template.as_deref())
+            .context("render dm page")?;
+        let f = Path::new("ornithology-dms.html");
+        tokio::fs::write(&f, &html)
+            .await
+            .context("write ornithology-dms.html")?;
+        eprintln!("wrote your dm transcripts to {}", f.display());
     }
-    let groups = serde_json::to_string(&groups).expect("serialize groups");
-
-    let data = serde_json::to_string(&lists_of_tweets).expect("serialize lists_of_tweets");
-    let html = format!(
-        r#"
-<!DOCTYPE html>
-<html lang="en">
-  <head>
-    <meta charset="utf-8">
-    <title>@{me} ornithology</title>
-    <style>
-      #tweets {{
-        display: flex;
-        flex-direction: row;
-        flex-wrap: nowrap;
-        gap: 0 2em;
-      }}
-      .list {{
-        width: 30em;
-        flex: none;
-      }}
-        .list h2 {{
-          text-align: center;
-          margin: 0;
-          border: 1px solid rgb(207, 217, 222);
-          border-radius: 12px;
-          background: white;
-          padding: 1em;
-        }}
-      #followers {{
-        list-style-type: none;
-        padding: 0;
-      }}
-        #followers li {{
-          display: block;
-          margin: 1em;
-          border: 1px solid rgb(207, 217, 222);
-          border-radius: 12px;
-          background: white;
-          padding: 1em;
-        }}
-          #followers li strong {{
-            margin-right: .5em;
-          }}
-          #followers li a + a::before {{
-            content: ",";
-            margin: 0 0.5ex;
-          }}
-    </style>
-  </head>
-  <body>
-    <ul id="followers"></ul>
-    <div id="tweets"></div>
-    <script charset="utf-8">
-    var data = {data};
-
-    var followers = document.getElementById('followers');
-    [['top_followers', 'Top followers'], ['neat_followers', 'Neat followers']].forEach(([id, title]) => {{
-      var li = document.createElement('li');
-      var s = document.createElement('strong');
-      s.textContent = title + ':';
-      li.appendChild(s);
-      for (var i in data[id]) {{
-        var u = data[id][i];
-        var a = document.createElement('a');
-        a.setAttribute('href', 'https://twitter.com/' + u);
-        a.innerText = '@' + u;
-        li.appendChild(a);
-      }}
-      followers.appendChild(li);
-    }});
-
-    var groups = {groups};
-    var tweets = document.getElementById('tweets');
-    groups.forEach(([id, title]) => {{
-      var d = document.createElement('div');
-      d.id = id;
-      d.classList.add("list");
-      var t = document.createElement('h2');
-      t.innerText = title;
-      d.appendChild(t);
-      tweets.appendChild(d);
-    }});
-  </script>
-  <script src="https://platform.twitter.com/widgets.js" charset="utf-8"></script>
-  <script charset="utf-8">
-    groups.forEach(([group, _]) => {{
-        var el = document.getElementById(group);
-        data[group].forEach(id => {{
-          twttr.widgets.createTweet(id, el);
-        }})
-    }});
-  </script>
-  </body>
-</html>
-"#,
-    );
-    let f = Path::new("ornithology.html");
-    tokio::fs::write(&f, &html)
-        .await
-        .context("write ornithology.html")?;
-    open::that(f).context("open generated page")?;
 
-    // TODO: add plots, like scatter plot of time/likes (prob. include id for easy reference)
+    // How each tweet (or thread) did by a given metric, for the terminal output.
+    let describe = |id: u64, metrics: &api::PublicTweetMetrics, score: f64| {
+        format!(
+            "https://twitter.com/{}/status/{} (scored {}: {} likes, {} rts, {} quotes, {} replies)",
+            me, id, score, metrics.likes, metrics.retweets, metrics.quotations, metrics.replies
+        )
+    };
+
+    // Metrics that depend on the time-based state.
+    // For example, how good was this tweet relative to other tweets at/up until that point in
+    // time. The idea being that what makes a tweet "notable" isn't "does it have a lot of likes",
+    // but "did it get a lot of likes relative to other tweets you twote back then"?
+    //
+    // This is always computed over your entire history, even if we're only reporting on some of
+    // it, so that the first tweets of a period are compared to the ones right before it.
+    tweets.sort_unstable_by_key(|t| t.created);
+    let detections: Vec<_> = config
+        .metrics
+        .iter()
+        .map(|metric| {
+            let series: Vec<notable::Sample> = tweets
+                .iter()
+                .map(|t| notable::Sample {
+                    time: t.created,
+                    value: metric.score(&t.metrics),
+                })
+                .collect();
+            let detector = metric.detector();
+            let detection = detector.detect(&series);
+            (detector, series, detection)
+        })
+        .collect();
+
+    // Threads are ranked by the first metric, applied to the sum of their tweets' metrics.
+    let primary = &config.metrics[0];
+    let thread_series: Vec<notable::Sample> = threads
+        .iter()
+        .map(|t| notable::Sample {
+            time: t.created,
+            value: primary.score(&t.metrics),
+        })
+        .collect();
+    let thread_detector = primary.detector();
+    let thread_detection = thread_detector.detect(&thread_series);
+    let by_id: HashMap<u64, &api::Tweet> = tweets.iter().map(|t| (t.id, t)).collect();
 
+    let periods = periods(args.since, args.until, args.per, &tweets);
+    if periods.is_empty() {
+        eprintln!("none of your tweets are in the given time range");
+    }
+    for period in &periods {
+        let within = |t: &time::OffsetDateTime| period.contains(t.date());
+        let mut lists = lists.clone();
+        if let Some(label) = &period.label {
+            println!("{}:", label);
+        }
+
+        for (metric, (detector, series, detection)) in config.metrics.iter().zip(&detections) {
+            println!("{}:", metric.notable_title().to_lowercase());
+            let entry = lists
+                .entry(format!("notable_{}", metric.name))
+                .or_insert_with(|| Vec::with_capacity(toptn));
+            for found in detection
+                .notable
+                .iter()
+                .filter(|n| within(&series[n.index].time))
+                .take(toptn)
+            {
+                let tweet = &tweets[found.index];
+                let explanation = detector.explain(found);
+                println!(
+                    "{}: {}",
+                    describe(tweet.id, &tweet.metrics, found.value),
+                    explanation
+                );
+                entry.push(report::Entry::Tweet(report::TweetEntry {
+                    score: Some(found.score),
+                    average: Some(found.baseline),
+                    explanation: Some(explanation),
+                    ..own_tweet(tweet)
+                }));
+            }
+        }
+
+        // Now to the boring "best/most of all time" bits:
+        let mut in_period: Vec<&api::Tweet> =
+            tweets.iter().filter(|t| within(&t.created)).collect();
+        for metric in &config.metrics {
+            println!("{}:", metric.top_title().to_lowercase());
+            let entry = lists
+                .entry(format!("top_{}", metric.name))
+                .or_insert_with(|| Vec::with_capacity(toptn));
+            in_period.sort_unstable_by(|a, b| {
+                metric
+                    .score(&a.metrics)
+                    .total_cmp(&metric.score(&b.metrics))
+            });
+            for tweet in in_period.iter().rev().take(toptn) {
+                let score = metric.score(&tweet.metrics);
+                println!("{}", describe(tweet.id, &tweet.metrics, score));
+                entry.push(report::Entry::Tweet(report::TweetEntry {
+                    score: Some(score),
+                    ..own_tweet(tweet)
+                }));
+            }
+        }
+
+        println!("top threads:");
+        let entry = lists
+            .entry("top_threads".to_string())
+            .or_insert_with(|| Vec::with_capacity(toptn));
+        let mut threads_in_period: Vec<&threads::Thread> =
+            threads.iter().filter(|t| within(&t.created)).collect();
+        threads_in_period.sort_unstable_by(|a, b| {
+            primary
+                .score(&a.metrics)
+                .total_cmp(&primary.score(&b.metrics))
+        });
+        for thread in threads_in_period.iter().rev().take(toptn) {
+            let score = primary.score(&thread.metrics);
+            println!(
+                "{} over {} tweets",
+                describe(thread.root, &thread.metrics, score),
+                thread.tweets.len()
+            );
+            entry.push(report::Entry::Tweet(report::TweetEntry {
+                score: Some(score),
+                ..own_tweet(by_id[&thread.root])
+            }));
+        }
+
+        println!("notable threads:");
+        let entry = lists
+            .entry("notable_threads".to_string())
+            .or_insert_with(|| Vec::with_capacity(toptn));
+        for found in thread_detection
+            .notable
+            .iter()
+            .filter(|n| within(&thread_series[n.index].time))
+            .take(toptn)
+        {
+            let thread = &threads[found.index];
+            let explanation = thread_detector.explain(found);
+            println!(
+                "{} over {} tweets: {}",
+                describe(thread.root, &thread.metrics, found.value),
+                thread.tweets.len(),
+                explanation
+            );
+            entry.push(report::Entry::Tweet(report::TweetEntry {
+                score: Some(found.score),
+                average: Some(found.baseline),
+                explanation: Some(explanation),
+                ..own_tweet(by_id[&thread.root])
+            }));
+        }
+
+        for group in tweet_groups.iter().chain(&user_groups) {
+            assert!(lists.contains_key(&group.id), "{}", group.id);
+        }
+
+        // Finally, some plots to give an overview of it all.
+        let mut charts = Vec::new();
+        let mut chart = |id: &str, title: &str, svg: Option<String>| {
+            if let Some(svg) = svg {
+                charts.push(report::charts::Chart {
+                    id: id.to_string(),
+                    title: title.to_string(),
+                    svg,
+                });
+            }
+        };
+        let plotted = &config.metrics[0].name;
+        let (_, series, detection) = &detections[0];
+        let engagement: Vec<_> = tweets
+            .iter()
+            .zip(series)
+            .zip(&detection.baselines)
+            .filter(|((t, _), _)| within(&t.created))
+            .map(|((t, sample), &average)| report::charts::Point {
+                id: t.id,
+                url: format!("https://twitter.com/{}/status/{}", me, t.id),
+                created: t.created,
+                value: sample.value,
+                average,
+            })
+            .collect();
+        chart(
+            "engagement",
+            &format!(
+                "How each tweet scored by {}, and the baseline used to find notable tweets",
+                plotted
+            ),
+            report::charts::scatter(&format!("{} over time", plotted), &engagement),
+        );
+        chart(
+            "tweets_per_month",
+            "Tweets per month",
+            report::charts::histogram(
+                "tweets per month",
+                &report::charts::per_month(in_period.iter().map(|t| t.created)),
+            ),
+        );
+        for (id, title, users) in [
+            (
+                "followers_followers",
+                "How many followers your followers have",
+                &followers,
+            ),
+            (
+                "following_followers",
+                "How many followers the accounts you follow have",
+                &following,
+            ),
+        ] {
+            if !users.is_empty() {
+                chart(
+                    id,
+                    title,
+                    report::charts::histogram(
+                        title,
+                        &report::charts::by_magnitude(users.iter().map(|u| u.metrics.followers)),
+                    ),
+                );
+            }
+        }
+
+        let mut f = match &args.output {
+            Some(f) => f.clone(),
+            None => PathBuf::from(match args.format {
+                Format::Html => "ornithology.html",
+                Format::Json => "ornithology.json",
+                Format::Markdown => "ornithology.md",
+            }),
+        };
+        if args.per.is_some() {
+            // Each period gets its own file, named after the period.
+            if let Some(label) = &period.label {
+                let mut name = f.file_stem().unwrap_or_default().to_os_string();
+                name.push("-");
+                name.push(label);
+                if let Some(ext) = f.extension() {
+                    name.push(".");
+                    name.push(ext);
+                }
+                f.set_file_name(name);
+            }
+        }
+        match args.format {
+            Format::Json => {
+                let document = report::json::Document {
+                    version: report::json::VERSION,
+                    generated: time::OffsetDateTime::now_utc(),
+                    account: report::json::Account {
+                        id: account.id,
+                        username: account.username.clone(),
+                        display_name: account.display_name.clone(),
+                    },
+                    period: period.label.clone().map(|label| report::json::Period {
+                        label,
+                        since: period.since.map(|d| d.to_string()),
+                        until: period.until.map(|d| d.to_string()),
+                    }),
+                    lists: lists.into_iter().collect(),
+                    like_volume: like_volume.clone(),
+                    relationships: report::json::Relationships {
+                        followers: follower_ids.len(),
+                        following: following_ids.len(),
+                        mutuals: mutuals.len(),
+                        not_following_back: not_following_back.len(),
+                        fans: fans.len(),
+                    },
+                    baselines: config
+                        .metrics
+                        .iter()
+                        .zip(&detections)
+                        .map(|(metric, (_, series, detection))| {
+                            let points = tweets
+                                .iter()
+                                .zip(series)
+                                .zip(&detection.baselines)
+                                .filter(|((t, _), _)| within(&t.created))
+                                .map(|((t, sample), &baseline)| report::json::BaselinePoint {
+                                    id: t.id,
+                                    created: t.created,
+                                    score: sample.value,
+                                    baseline,
+                                })
+                                .collect();
+                            (metric.name.clone(), points)
+                        })
+                        .collect(),
+                };
+                let json = serde_json::to_vec_pretty(&document).expect("serialize report");
+                tokio::fs::write(&f, json)
+                    .await
+                    .with_context(|| format!("write {}", f.display()))?;
+                eprintln!("wrote report to {}", f.display());
+            }
+            Format::Html | Format::Markdown => {
+                // With the static renderer, everything we show about a tweet has to come from what we
+                // have locally. The embed renderer also uses it as a fallback.
+                let mut cards: HashMap<u64, report::Card> = tweet_groups
+                    .iter()
+                    .flat_map(|group| &lists[&group.id])
+                    .filter_map(|e| match e {
+                        report::Entry::Tweet(t) => Some((t.id, t.card.clone())),
+                        report::Entry::User(_) => None,
+                    })
+                    .collect();
+
+                let media_mode = args.media.unwrap_or(match args.renderer {
+                    report::Renderer::Static => MediaMode::Embed,
+                    report::Renderer::Embed => MediaMode::Copy,
+                });
+                if media_mode != MediaMode::None {
+                    // Only tweets that actually end up on the page need their media.
+                    let shown: HashSet<u64> = cards.keys().copied().collect();
+                    let dir = f.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+                    let media = tokio::task::spawn_blocking(move || {
+                        collect_media(archive, &shown, media_mode, &dir)
+                    })
+                    .await
+                    .context("spawn blocking")?
+                    .context("collect tweet media")?;
+                    for (id, thumbnails) in media {
+                        if let Some(card) = cards.get_mut(&id) {
+                            card.media = thumbnails;
+                        }
+                    }
+                }
+
+                let context = report::Context {
+                    me: me.to_string(),
+                    renderer: args.renderer,
+                    lists: lists
+                        .iter()
+                        .map(|(id, list)| (id.clone(), list.iter().map(|e| e.key()).collect()))
+                        .collect(),
+                    period: period.label.clone(),
+                    tweet_groups: tweet_groups.clone(),
+                    user_groups: user_groups.clone(),
+                    like_volume: like_volume.clone(),
+                    cards: cards
+                        .into_iter()
+                        .map(|(id, card)| (id.to_string(), card))
+                        .collect(),
+                    explanations: lists
+                        .iter()
+                        .map(|(id, list)| {
+                            let why = list.iter().filter_map(|e| match e {
+                                report::Entry::Tweet(t) => {
+                                    Some((t.id.to_string(), t.explanation.clone()?))
+                                }
+                                report::Entry::User(_) => None,
+                            });
+                            (id.clone(), why.collect())
+                        })
+                        .collect(),
+                    threads: lists
+                        .values()
+                        .flatten()
+                        .filter_map(|e| match e {
+                            report::Entry::Tweet(t) => Some((t.id.to_string(), t.thread.clone()?)),
+                            report::Entry::User(_) => None,
+                        })
+                        .collect(),
+                    charts,
+                    users: lists
+                        .values()
+                        .flatten()
+                        .filter_map(|e| match e {
+                            report::Entry::User(u) => Some((u.username.clone(), u.user.clone()?)),
+                            report::Entry::Tweet(_) => None,
+                        })
+                        .collect(),
+                };
+                let template = match args.format {
+                    Format::Markdown => "report.md",
+                    _ => "index.html",
+                };
+                let out = report::render(template, &context, args.template.as_deref())
+                    .context("render report")?;
+                tokio::fs::write(&f, &out)
+                    .await
+                    .with_context(|| format!("write {}", f.display()))?;
+                if args.format == Format::Html && periods.len() == 1 {
+                    open::that(&f).context("open generated page")?;
+                } else {
+                    eprintln!("wrote report to {}", f.display());
+                }
+            }
+        }
+    }
     Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Gather the archived media for the given tweets, either by copying it next to the page or by
+/// turning it into data URIs.
+///
+/// Copied media goes in `ornithology-media/` in `next_to`, which should be the directory the page
+/// is written to.
+fn collect_media(
+    archive: &Path,
+    tweets: &HashSet<u64>,
+    mode: MediaMode,
+    next_to: &Path,
+) -> anyhow::Result<HashMap<u64, Vec<report::Thumbnail>>> {
+    const DIR: &str = "ornithology-media";
+    let dir = next_to.join(DIR);
+
+    let mut archive = archive::open(archive).context("read twitter archive")?;
+    let mut index = archive.media(archive::DataType::Tweets);
+    let mut thumbnails = HashMap::new();
+    for &id in tweets {
+        let files = match index.remove(&id) {
+            Some(files) => files,
+            None => continue,
+        };
+        let mut thumbs = Vec::with_capacity(files.len());
+        for file in files {
+            let mime = match file.mime_type() {
+                Some(mime) => mime,
+                // We wouldn't know how to show it anyway.
+                None => continue,
+            };
+            let contents = archive.read(&file.name)?;
+            let src = match mode {
+                MediaMode::Copy => {
+                    std::fs::create_dir_all(&dir)
+                        .with_context(|| format!("create {}", dir.display()))?;
+                    let path = dir.join(file.file_name());
+                    std::fs::write(&path, contents)
+                        .with_context(|| format!("write {}", path.display()))?;
+                    format!("{}/{}", DIR, file.file_name())
+                }
+                MediaMode::Embed => format!("data:{};base64,{}", mime, base64::encode(contents)),
+                MediaMode::None => unreachable!("media is not collected in mode none"),
+            };
+            thumbs.push(report::Thumbnail {
+                src,
+                video: file.is_video(),
+            });
+        }
+        thumbnails.insert(id, thumbs);
+    }
+    Ok(thumbnails)
+}
+
+/// Format a duration the way a human would say it, like "3h 20m".
+fn humanize(d: time::Duration) -> String {
+    if d.whole_days() > 0 {
+        format!("{}d {}h", d.whole_days(), d.whole_hours() % 24)
+    } else if d.whole_hours() > 0 {
+        format!("{}h {}m", d.whole_hours(), d.whole_minutes() % 60)
+    } else if d.whole_minutes() > 0 {
+        format!("{}m", d.whole_minutes())
+    } else {
+        format!("{}s", d.whole_seconds())
+    }
+}
+
+/// Gather what the DM page shows: statistics and a transcript of every conversation.
+fn dm_context(
+    me: &str,
+    stats: &dms::Stats,
+    conversations: &[archive::ConversationData],
+    name: impl Fn(u64) -> String,
+) -> report::dms::Context {
+    let mut transcripts: HashMap<&str, Vec<report::dms::Line>> = conversations
+        .iter()
+        .map(|c| {
+            let mut lines: Vec<_> = c
+                .messages()
+                .map(|m| report::dms::Line {
+                    from: name(m.sender_id),
+                    at: m.created.to_string(),
+                    text: m.text.clone(),
+                })
+                .collect();
+            // The archive lists the most recent message first.
+            lines.reverse();
+            (&*c.id, lines)
+        })
+        .collect();
+    // Go in the order of the stats, so that the longest conversations come first.
+    let transcripts = stats
+        .conversations
+        .iter()
+        .filter_map(|c| {
+            Some(report::dms::Transcript {
+                lines: transcripts.remove(&*c.id)?,
+                with: c
+                    .others
+                    .iter()
+                    .map(|&id| name(id))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+        })
+        .collect();
+
+    report::dms::Context {
+        me: me.to_string(),
+        summary: report::dms::Summary {
+            correspondents: stats
+                .correspondents
+                .iter()
+                .map(|&(id, n)| (name(id), n))
+                .collect(),
+            my_response_time: stats.my_response_time.map(humanize),
+            their_response_time: stats.their_response_time.map(humanize),
+            busiest_months: stats
+                .busiest_months
+                .iter()
+                .map(|(m, n)| (format!("{}-{:02}", m.year(), m.month() as u8), *n))
+                .collect(),
+        },
+        transcripts,
+    }
+}
+
+/// Everything we know about the account, from the archive and (unless offline) the Twitter API.
+#[derive(Debug)]
 struct Loaded {
-    me: String,
-    old_rt_ids: Vec<u64>,
+    account: archive::Account,
+    old_rts: Vec<OldRetweet>,
     tweets: Vec<api::Tweet>,
+    /// What the archive says about each of your tweets beyond what's in `tweets`.
+    archived: HashMap<u64, Archived>,
     followers: Vec<api::User>,
+    following: Vec<api::User>,
+    follower_ids: HashSet<u64>,
+    following_ids: HashSet<u64>,
+    likes: Vec<archive::LikeData>,
+    /// Only loaded if DM analysis was asked for.
+    conversations: Vec<archive::ConversationData>,
 }
 
-async fn load(use_cache: bool, archive: &'static Path) -> anyhow::Result<Loaded> {
-    let cache_file = Path::new("cache.json");
-    if use_cache && cache_file.exists() {
-        let s = tokio::fs::read(&cache_file)
-            .await
-            .with_context(|| format!("read {}", cache_file.display()))?;
-        return Ok(serde_json::from_slice(&s)
-            .with_context(|| format!("parse {}", cache_file.display()))?);
+/// Every archived tweet, along with its metrics if the Twitter API returned it, in the order they
+/// were posted.
+fn export_tweets<'a>(
+    archived: &'a HashMap<u64, Archived>,
+    tweets: &'a [api::Tweet],
+) -> Vec<export::Tweet<'a>> {
+    let metrics: HashMap<u64, &api::PublicTweetMetrics> =
+        tweets.iter().map(|t| (t.id, &t.metrics)).collect();
+    let mut all: Vec<_> = archived
+        .iter()
+        .map(|(&id, a)| export::Tweet {
+            id,
+            created: a.created,
+            text: &a.text,
+            kind: &a.kind,
+            metrics: metrics.get(&id).copied(),
+        })
+        .collect();
+    all.sort_unstable_by_key(|t| (t.created, t.id));
+    all
+}
+
+/// One of your native retweets.
+#[derive(Debug)]
+struct OldRetweet {
+    /// The id of the retweet itself.
+    id: u64,
+    created: time::OffsetDateTime,
+    of: archive::RetweetOf,
+    /// The retweeted text, which the archive may have truncated.
+    text: String,
+}
+
+impl OldRetweet {
+    /// Where to link to for this retweet, given the username of the archive's owner.
+    fn link(&self, me: &str) -> archive::TweetRef {
+        match self.of.id {
+            Some(id) => archive::TweetRef {
+                author: self.of.author.clone(),
+                id,
+            },
+            // If we don't know the id of the original tweet, we can still link to the retweet
+            // itself, which Twitter will redirect to the original.
+            None => archive::TweetRef {
+                author: me.to_string(),
+                id: self.id,
+            },
+        }
     }
+}
+
+/// The parts of one of your tweets that only the archive knows about.
+#[derive(Debug)]
+struct Archived {
+    created: time::OffsetDateTime,
+    /// See [`archive::TweetData::display_text`].
+    text: String,
+    kind: archive::Kind,
+}
+
+/// The parts of [`Loaded`] that come from the Twitter API.
+///
+/// These take a long time to fetch, so we cache them in `cache.json` between runs. Everything else
+/// is (re-)parsed from the archive on every run.
+#[derive(Debug, Serialize, Deserialize)]
+struct Hydrated {
+    /// The user we were authenticated as when talking to the API.
+    me: String,
+    tweets: Vec<api::Tweet>,
+    /// The ids of the tweets we asked the API about.
+    ///
+    /// This can be more than `tweets`, since the API doesn't return deleted tweets. Caches from
+    /// before tweets could be filtered don't have this, but they asked about every tweet.
+    #[serde(default)]
+    requested: Option<HashSet<u64>>,
+    followers: Vec<api::User>,
+    following: Vec<api::User>,
+}
+
+async fn load(
+    use_cache: bool,
+    offline: bool,
+    dms: bool,
+    filter: filter::Filter,
+    archive: &'static Path,
+) -> anyhow::Result<Loaded> {
+    // Everything from the archive is loaded in one go, and then whatever the API knows is filled
+    // in after.
+    let mut loaded = tokio::task::spawn_blocking(move || {
+        let mut archive = archive::open(archive).context("read twitter archive")?;
+        eprintln!("archive format: {}", archive.format());
 
-    let (old_rt_ids, follower_ids, tweet_ids) = tokio::task::spawn_blocking(|| {
-        let fname = Path::new(archive);
-        let zipfile = std::fs::File::open(&fname).context("open twitter archive")?;
-        let mut archive = zip::ZipArchive::new(zipfile).context("open twitter archive as zip")?;
+        // We need to know whose archive this is to tell self-replies from other replies.
+        let account = archive.account().context("read account details")?;
+        eprintln!("archive of: @{} ({})", account.username, account.id);
+        let my_id = account.id;
 
-        let followers: Vec<u64> = archive::parse(
+        let followers: HashSet<u64> = archive::parse(
             &mut archive,
-            "data/follower.js",
+            archive::DataType::Follower,
             |archive::Follower::One { id }| Some(id),
         )
         .context("extract follower list")?;
 
+        // Not everyone follows anyone.
+        let following: HashSet<u64> = if archive.has(archive::DataType::Following) {
+            archive::parse(
+                &mut archive,
+                archive::DataType::Following,
+                |archive::Following::One { id }| Some(id),
+            )
+            .context("extract following list")?
+        } else {
+            HashSet::new()
+        };
+
+        // We construct tweets from the archive's engagement counts here even if we're not in
+        // offline mode, since we'll need to iterate over the ids anyway.
         let mut oldies = Vec::new();
-        let tweets: Vec<u64> = archive::parse(
+        let mut archived = HashMap::new();
+        let mut filtered = 0;
+        let tweets: Vec<api::Tweet> = archive::parse(
             &mut archive,
-            "data/tweet.js",
-            |archive::Tweet::One { id, text }| {
-                if text.starts_with("RT @") {
-                    oldies.push(id);
+            archive::DataType::Tweets,
+            |archive::Tweet::One(tweet)| {
+                let kind = tweet.kind(my_id);
+                if let archive::Kind::Retweet { of } = kind {
+                    let text = tweet.display_text();
+                    let prefix = format!("RT @{}: ", of.author);
+                    oldies.push(OldRetweet {
+                        id: tweet.id,
+                        created: tweet.created,
+                        text: text.strip_prefix(&prefix).unwrap_or(&text).to_string(),
+                        of,
+                    });
+                    None
+                } else if !filter.keep(&tweet, &kind) {
+                    filtered += 1;
                     None
                 } else {
-                    Some(id)
+                    archived.insert(
+                        tweet.id,
+                        Archived {
+                            created: tweet.created,
+                            text: tweet.display_text(),
+                            kind,
+                        },
+                    );
+                    Some(api::Tweet::from(&tweet))
                 }
             },
         )
-        .context("extract follower list")?;
+        .context("extract tweet list")?;
+        if !filter.is_empty() {
+            eprintln!(
+                "analyzing {} tweets, and skipping {} that don't match the filters",
+                tweets.len(),
+                filtered
+            );
+        }
+
+        // Not everyone has liked anything.
+        let likes: Vec<archive::LikeData> = if archive.has(archive::DataType::Like) {
+            archive::parse(
+                &mut archive,
+                archive::DataType::Like,
+                |archive::Like::One(like)| Some(like),
+            )
+            .context("extract likes")?
+        } else {
+            Vec::new()
+        };
+
+        // DMs are only parsed if explicitly asked for, since they're private.
+        let mut conversations: Vec<archive::ConversationData> = Vec::new();
+        if dms {
+            for kind in [
+                archive::DataType::DirectMessages,
+                archive::DataType::DirectMessagesGroup,
+            ] {
+                if archive.has(kind) {
+                    let more: Vec<_> =
+                        archive::parse(&mut archive, kind, |archive::Conversation::One(c)| Some(c))
+                            .with_context(|| format!("extract {}", kind))?;
+                    conversations.extend(more);
+                }
+            }
+        }
 
-        Ok::<_, anyhow::Error>((oldies, followers, tweets))
+        Ok::<_, anyhow::Error>(Loaded {
+            account,
+            old_rts: oldies,
+            tweets,
+            archived,
+            followers: Vec::new(),
+            following: Vec::new(),
+            follower_ids: followers,
+            following_ids: following,
+            likes,
+            conversations,
+        })
     })
     .await
     .context("spawn blocking")??;
+    let tweets = std::mem::take(&mut loaded.tweets);
+
+    let hydrated = if offline {
+        if !loaded.follower_ids.is_empty() || !loaded.following_ids.is_empty() {
+            eprintln!(
+                "skipping {} followers and {} followed accounts since their metrics aren't in the archive",
+                loaded.follower_ids.len(),
+                loaded.following_ids.len()
+            );
+        }
+        Hydrated {
+            me: loaded.account.username.clone(),
+            tweets,
+            requested: None,
+            followers: Vec::new(),
+            following: Vec::new(),
+        }
+    } else {
+        hydrate(
+            use_cache,
+            tweets,
+            &loaded.follower_ids,
+            &loaded.following_ids,
+        )
+        .await
+        .context("fetch data from the twitter api")?
+    };
+    if !hydrated.me.eq_ignore_ascii_case(&loaded.account.username) {
+        eprintln!(
+            "warning: the archive is for @{}, but the Twitter API data is for @{}",
+            loaded.account.username, hydrated.me
+        );
+    }
+
+    loaded.tweets = hydrated.tweets;
+    loaded.followers = hydrated.followers;
+    loaded.following = hydrated.following;
+    Ok(loaded)
+}
+
+async fn hydrate(
+    use_cache: bool,
+    tweets: Vec<api::Tweet>,
+    follower_ids: &HashSet<u64>,
+    following_ids: &HashSet<u64>,
+) -> anyhow::Result<Hydrated> {
+    let cache_file = Path::new("cache.json");
+    if use_cache && cache_file.exists() {
+        let s = tokio::fs::read(&cache_file)
+            .await
+            .with_context(|| format!("read {}", cache_file.display()))?;
+        match serde_json::from_slice::<Hydrated>(&s) {
+            Ok(mut hydrated) => {
+                // The cache may be from a run with different filters, in which case it has
+                // tweets we don't want, or misses tweets we do.
+                let want: HashSet<u64> = tweets.iter().map(|t| t.id).collect();
+                if hydrated
+                    .requested
+                    .as_ref()
+                    .is_none_or(|requested| want.is_subset(requested))
+                {
+                    hydrated.tweets.retain(|t| want.contains(&t.id));
+                    return Ok(hydrated);
+                }
+                eprintln!(
+                    "ignoring {} since it is missing some of the tweets to analyze",
+                    cache_file.display()
+                );
+            }
+            Err(e) => {
+                // Most likely the cache was written by an older version that didn't store
+                // everything we now need, so we'll just have to fetch it all again.
+                eprintln!(
+                    "ignoring {} since it could not be parsed: {}",
+                    cache_file.display(),
+                    e
+                );
+            }
+        }
+    }
 
     let client_id = ClientId::new("SUtlNTYydEhnVDJEOW5uSmh3Q0g6MTpjaQ".to_string());
     let mut client = api::Client::new(client_id)
@@ -411,23 +1537,59 @@ async fn load(use_cache: bool, archive: &'static Path) -> anyhow::Result<Loaded>
     eprintln!("whoami: @{} ({})", whoami.username, whoami.id);
 
     // Now get stats about each tweet:
-    let tweets = client.tweets(tweet_ids).await.context("fetch tweets")?;
+    let requested: HashSet<u64> = tweets.iter().map(|t| t.id).collect();
+    let tweets = client
+        .tweets(tweets.iter().map(|t| t.id))
+        .await
+        .context("fetch tweets")?;
 
-    // and about each follower:
-    let followers = client.users(follower_ids).await.context("fetch follower")?;
+    // and about each follower and followed account. Mutuals are in both sets, so we make sure to
+    // only fetch them once.
+    let users = client
+        .users(
+            follower_ids
+                .union(following_ids)
+                .copied()
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .context("fetch users")?;
+    let (mut followers, mut following) = (Vec::new(), Vec::new());
+    for user in users {
+        match (
+            follower_ids.contains(&user.id),
+            following_ids.contains(&user.id),
+        ) {
+            (true, true) => {
+                following.push(user.clone());
+                followers.push(user);
+            }
+            (true, false) => followers.push(user),
+            (false, true) => following.push(user),
+            (false, false) => {
+                // We only asked for followers and followed accounts, but the API is free to
+                // disagree.
+                eprintln!(
+                    "warning: skipping @{}, who is neither a follower nor followed",
+                    user.username
+                );
+            }
+        }
+    }
 
-    let loaded = Loaded {
+    let hydrated = Hydrated {
         me: whoami.username,
-        old_rt_ids,
         tweets,
+        requested: Some(requested),
         followers,
+        following,
     };
     tokio::fs::write(
         &cache_file,
-        &serde_json::to_vec(&loaded).context("serialize cache.json")?,
+        &serde_json::to_vec(&hydrated).context("serialize cache.json")?,
     )
     .await
     .with_context(|| format!("write {}", cache_file.display()))?;
 
-    Ok(loaded)
+    Ok(hydrated)
 }
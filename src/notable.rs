@@ -0,0 +1,670 @@
+//! Finding the points in a time series that stand out from what came before them.
+//!
+//! This is what picks the "notable" tweets: a tweet isn't notable because it did well, but because
+//! it did well compared to your other tweets at the time. What "at the time" means is up to the
+//! [`Baseline`], which summarizes the samples before each one into a single value to compare with.
+//!
+//! Baselines over a window of [`Window::Days`] go by calendar time: they're computed from the
+//! average of each day in the window rather than from each sample, so a burst of fifty tweets in
+//! a single day counts for no more than a day with one tweet. Samples with nothing in their window
+//! have nothing to be compared to, and so are never notable.
+//!
+//! ```
+//! use ornithology_cli::notable::{Baseline, Detector, Sample, Window};
+//! use time::macros::datetime;
+//!
+//! let start = datetime!(2022-01-01 0:00 UTC);
+//! let series: Vec<_> = [10.0, 12.0, 9.0, 11.0, 80.0, 10.0]
+//!     .into_iter()
+//!     .enumerate()
+//!     .map(|(i, value)| Sample {
+//!         time: start + time::Duration::days(i as i64),
+//!         value,
+//!     })
+//!     .collect();
+//! let detector = Detector {
+//!     baseline: Baseline::Mean {
+//!         window: Window::Days(30),
+//!     },
+//!     floor: 20.0,
+//!     factor: 2.0,
+//! };
+//! let found = detector.detect(&series);
+//! assert_eq!(found.notable.len(), 1);
+//! assert_eq!(found.notable[0].index, 4);
+//! assert_eq!(
+//!     detector.explain(&found.notable[0]),
+//!     "7.6× the average of the previous 30 days (4 tweets), which was 10.50"
+//! );
+//! ```
+
+use serde::Deserialize;
+use std::fmt;
+
+/// A single point of the series, like the score of one tweet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub time: time::OffsetDateTime,
+    pub value: f64,
+}
+
+/// Which earlier samples a baseline is computed over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Window {
+    /// The given number of samples right before.
+    Tweets(usize),
+    /// The samples from the given number of days before, averaged per (UTC) day.
+    Days(u32),
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Window::Tweets(1) => write!(f, "previous tweet"),
+            Window::Tweets(n) => write!(f, "previous {} tweets", n),
+            Window::Days(1) => write!(f, "previous day"),
+            Window::Days(n) => write!(f, "previous {} days", n),
+        }
+    }
+}
+
+/// How to summarize the samples before a given sample.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Baseline {
+    /// An exponential moving average over all earlier samples, starting at zero.
+    ///
+    /// Each sample moves the average `alpha` of the way towards its value, so higher values of
+    /// `alpha` forget the past faster.
+    Ema { alpha: f64 },
+    /// The mean of the samples in the window.
+    Mean { window: Window },
+    /// The median of the samples in the window.
+    ///
+    /// Unlike the average, this isn't thrown off by the odd sample that did extremely well.
+    Median { window: Window },
+    /// The mean of the samples in the window, with samples scored by how many standard deviations
+    /// above it they are rather than how many times higher.
+    #[serde(rename = "zscore")]
+    ZScore { window: Window },
+}
+
+/// The trailing 30 days.
+impl Default for Baseline {
+    fn default() -> Self {
+        Baseline::Mean {
+            window: Window::Days(30),
+        }
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Baseline::Ema { alpha } => write!(f, "moving average (alpha {})", alpha),
+            Baseline::Mean { window } | Baseline::ZScore { window } => {
+                write!(f, "average of the {}", window)
+            }
+            Baseline::Median { window } => write!(f, "median of the {}", window),
+        }
+    }
+}
+
+/// Decides which samples are notable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detector {
+    pub baseline: Baseline,
+    /// Samples with values at or below this are never notable.
+    pub floor: f64,
+    /// How much a sample needs to beat its baseline by to be notable.
+    ///
+    /// This is a multiple of the baseline, except for [`Baseline::ZScore`] where it is the number
+    /// of standard deviations above the mean.
+    pub factor: f64,
+}
+
+/// A sample that stood out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Notable {
+    /// Where in the series the sample is.
+    pub index: usize,
+    pub value: f64,
+    /// What the sample was compared against.
+    pub baseline: f64,
+    /// How many earlier samples the baseline was computed from.
+    pub samples: usize,
+    /// How far above the baseline the sample was, in the same unit as [`Detector::factor`].
+    ///
+    /// This is infinite if the baseline is zero (or has no spread, for z-scores).
+    pub score: f64,
+}
+
+/// The result of [`Detector::detect`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// The notable samples, most notable first.
+    pub notable: Vec<Notable>,
+    /// The baseline at each sample of the series, for plotting.
+    ///
+    /// This is zero for samples with nothing in their window.
+    pub baselines: Vec<f64>,
+}
+
+impl Detector {
+    /// Find the notable samples in `series`, which must be sorted by time.
+    pub fn detect(&self, series: &[Sample]) -> Detection {
+        let mut notable = Vec::new();
+        let mut baselines = Vec::with_capacity(series.len());
+        let mut ema = 0.0;
+        for (i, sample) in series.iter().enumerate() {
+            let (baseline, score, samples) = match self.baseline {
+                Baseline::Ema { alpha } => {
+                    let baseline = ema;
+                    ema = alpha * sample.value + (1.0 - alpha) * ema;
+                    (baseline, sample.value / baseline, i)
+                }
+                Baseline::Mean { window } | Baseline::Median { window } => {
+                    let (values, samples) = window_before(series, i, window);
+                    let baseline = if let Baseline::Mean { .. } = self.baseline {
+                        mean_and_sd(&values).0
+                    } else {
+                        median(values)
+                    };
+                    (baseline, sample.value / baseline, samples)
+                }
+                Baseline::ZScore { window } => {
+                    let (values, samples) = window_before(series, i, window);
+                    let (mean, sd) = mean_and_sd(&values);
+                    let score = if sd > 0.0 {
+                        (sample.value - mean) / sd
+                    } else if sample.value > mean {
+                        f64::INFINITY
+                    } else {
+                        0.0
+                    };
+                    (mean, score, samples)
+                }
+            };
+            baselines.push(baseline);
+
+            let beats = match self.baseline {
+                Baseline::ZScore { .. } => score > self.factor,
+                Baseline::Ema { .. } | Baseline::Mean { .. } | Baseline::Median { .. } => {
+                    sample.value > self.factor * baseline
+                }
+            };
+            let comparable = samples != 0 || matches!(self.baseline, Baseline::Ema { .. });
+            if comparable && sample.value > self.floor && beats {
+                notable.push(Notable {
+                    index: i,
+                    value: sample.value,
+                    baseline,
+                    samples,
+                    score,
+                });
+            }
+        }
+
+        // Scores that only differ by rounding errors are considered equal, and then the higher
+        // value goes first.
+        let rounded = |n: &Notable| (1000.0 * n.score).round();
+        notable.sort_unstable_by(|a, b| {
+            rounded(b)
+                .total_cmp(&rounded(a))
+                .then_with(|| b.value.total_cmp(&a.value))
+                .then_with(|| a.index.cmp(&b.index))
+        });
+        Detection { notable, baselines }
+    }
+
+    /// Explain why a sample this detector found was notable, like "3.2× the average of the
+    /// previous 30 days (12 tweets), which was 14.50".
+    pub fn explain(&self, notable: &Notable) -> String {
+        // How many tweets a window of days covered isn't obvious, so we say.
+        let what = match self.baseline {
+            Baseline::Mean {
+                window: Window::Days(_),
+            }
+            | Baseline::Median {
+                window: Window::Days(_),
+            }
+            | Baseline::ZScore {
+                window: Window::Days(_),
+            } => format!(
+                "the {} ({} tweet{})",
+                self.baseline,
+                notable.samples,
+                if notable.samples == 1 { "" } else { "s" }
+            ),
+            _ => format!("the {}", self.baseline),
+        };
+        match self.baseline {
+            _ if notable.score.is_infinite() && notable.baseline == 0.0 => {
+                format!("{} was 0", capitalize(&what))
+            }
+            Baseline::ZScore { .. } if notable.score.is_infinite() => format!(
+                "{} was {:.2}, and all of them scored the same",
+                capitalize(&what),
+                notable.baseline
+            ),
+            Baseline::ZScore { .. } => format!(
+                "{:.1} standard deviations above {}, which was {:.2}",
+                notable.score, what, notable.baseline
+            ),
+            _ => format!(
+                "{:.1}× {}, which was {:.2}",
+                notable.score, what, notable.baseline
+            ),
+        }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The values to compute the baseline for the sample at `i` from, and how many samples they
+/// came from.
+fn window_before(series: &[Sample], i: usize, window: Window) -> (Vec<f64>, usize) {
+    let before = &series[..i];
+    match window {
+        Window::Tweets(n) => {
+            let before = &before[before.len().saturating_sub(n)..];
+            (before.iter().map(|s| s.value).collect(), before.len())
+        }
+        Window::Days(days) => {
+            let since = series[i].time - time::Duration::days(i64::from(days));
+            let start = before.partition_point(|s| s.time < since);
+            let before = &before[start..];
+            let mut days: Vec<(time::Date, f64, usize)> = Vec::new();
+            for s in before {
+                let date = s.time.to_offset(time::UtcOffset::UTC).date();
+                match days.last_mut() {
+                    Some((d, sum, n)) if *d == date => {
+                        *sum += s.value;
+                        *n += 1;
+                    }
+                    _ => days.push((date, s.value, 1)),
+                }
+            }
+            let values = days.into_iter().map(|(_, sum, n)| sum / n as f64).collect();
+            (values, before.len())
+        }
+    }
+}
+
+/// The median of `values`, or zero if there are none.
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_unstable_by(f64::total_cmp);
+    match values.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => values[n / 2],
+        n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+    }
+}
+
+/// The mean and (population) standard deviation of `values`, or zeros if there are none.
+fn mean_and_sd(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    /// One sample per day with the given values.
+    fn daily(values: &[f64]) -> Vec<Sample> {
+        let start = datetime!(2020-01-01 12:00 UTC);
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Sample {
+                time: start + time::Duration::days(i as i64),
+                value,
+            })
+            .collect()
+    }
+
+    fn indices(detection: &Detection) -> Vec<usize> {
+        detection.notable.iter().map(|n| n.index).collect()
+    }
+
+    fn detector(baseline: Baseline) -> Detector {
+        Detector {
+            baseline,
+            floor: 10.0,
+            factor: 2.0,
+        }
+    }
+
+    #[test]
+    fn empty() {
+        for baseline in [
+            Baseline::default(),
+            Baseline::Median {
+                window: Window::Tweets(3),
+            },
+            Baseline::ZScore {
+                window: Window::Days(30),
+            },
+        ] {
+            let found = detector(baseline).detect(&[]);
+            assert!(found.notable.is_empty());
+            assert!(found.baselines.is_empty());
+        }
+    }
+
+    #[test]
+    fn ema_baselines() {
+        let series = daily(&[20.0, 20.0, 40.0, 0.0]);
+        let found = detector(Baseline::Ema { alpha: 0.5 }).detect(&series);
+        assert_eq!(found.baselines, [0.0, 10.0, 15.0, 27.5]);
+
+        let found = detector(Baseline::Ema { alpha: 1.0 }).detect(&series);
+        assert_eq!(found.baselines, [0.0, 20.0, 20.0, 40.0]);
+    }
+
+    #[test]
+    fn ema_spike() {
+        let series = daily(&[12.0, 12.0, 12.0, 12.0, 12.0, 120.0, 12.0]);
+        let found = detector(Baseline::Ema { alpha: 0.5 }).detect(&series);
+        // The first sample beats the initial zero average, and then the spike stands out.
+        assert_eq!(indices(&found), [0, 5]);
+        assert_eq!(found.notable[0].score, f64::INFINITY);
+        let spike = found.notable[1];
+        assert_eq!(spike.value, 120.0);
+        assert!((spike.baseline - 12.0).abs() < 0.5);
+        assert!((spike.score - 10.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn floor() {
+        // A tweet going from 1 to 5 likes is a big jump, but not interesting.
+        let series = daily(&[1.0, 1.0, 1.0, 5.0, 1.0]);
+        let found = detector(Baseline::Ema { alpha: 0.5 }).detect(&series);
+        assert!(found.notable.is_empty());
+    }
+
+    #[test]
+    fn most_notable_first() {
+        let series = daily(&[20.0, 20.0, 60.0, 20.0, 20.0, 200.0, 20.0, 20.0, 100.0]);
+        let found = detector(Baseline::Median {
+            window: Window::Tweets(2),
+        })
+        .detect(&series);
+        assert_eq!(indices(&found), [5, 8, 2]);
+        let scores: Vec<f64> = found.notable.iter().map(|n| n.score).collect();
+        assert_eq!(scores, [10.0, 5.0, 3.0]);
+    }
+
+    #[test]
+    fn median_ignores_outliers() {
+        // With an average, the 1000 would make the 50 after it look like nothing special.
+        let series = daily(&[20.0, 20.0, 1000.0, 20.0, 50.0]);
+        let median = Baseline::Median {
+            window: Window::Tweets(3),
+        };
+        let found = detector(median).detect(&series);
+        assert_eq!(found.baselines, [0.0, 20.0, 20.0, 20.0, 20.0]);
+        assert_eq!(indices(&found), [2, 4]);
+
+        let found = detector(Baseline::Ema { alpha: 0.5 }).detect(&series);
+        assert!(!indices(&found).contains(&4));
+    }
+
+    #[test]
+    fn median_of_even_window() {
+        let series = daily(&[10.0, 30.0, 0.0]);
+        let found = detector(Baseline::Median {
+            window: Window::Tweets(2),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines, [0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn median_over_days() {
+        let start = datetime!(2020-01-01 12:00 UTC);
+        let at = |days: i64, value: f64| Sample {
+            time: start + time::Duration::days(days),
+            value,
+        };
+        // A busy week long ago shouldn't count towards what's normal a month later.
+        let series = [
+            at(0, 100.0),
+            at(1, 100.0),
+            at(2, 100.0),
+            at(40, 30.0),
+            at(41, 30.0),
+            at(42, 70.0),
+        ];
+        let found = detector(Baseline::Median {
+            window: Window::Days(30),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines, [0.0, 100.0, 100.0, 0.0, 30.0, 30.0]);
+        // The first tweet after the break has nothing to compare to, so it isn't notable.
+        assert_eq!(indices(&found), [5]);
+
+        // Whereas counting the last 5 tweets, the busy week still dominates.
+        let found = detector(Baseline::Median {
+            window: Window::Tweets(5),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines[5], 100.0);
+        assert!(found.notable.is_empty());
+    }
+
+    #[test]
+    fn days_window_includes_its_start() {
+        let start = datetime!(2020-01-01 12:00 UTC);
+        let series = [
+            Sample {
+                time: start,
+                value: 40.0,
+            },
+            Sample {
+                time: start + time::Duration::days(1),
+                value: 0.0,
+            },
+        ];
+        let found = detector(Baseline::Median {
+            window: Window::Days(1),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines, [0.0, 40.0]);
+    }
+
+    #[test]
+    fn nothing_to_compare_to() {
+        let series = daily(&[50.0, 50.0]);
+        for baseline in [
+            Baseline::Mean {
+                window: Window::Days(30),
+            },
+            Baseline::Median {
+                window: Window::Tweets(3),
+            },
+            Baseline::ZScore {
+                window: Window::Tweets(3),
+            },
+        ] {
+            let found = detector(baseline).detect(&series);
+            assert_eq!(found.baselines[0], 0.0);
+            assert!(!indices(&found).contains(&0), "{:?}", baseline);
+        }
+        // The moving average starts at zero, so the first sample is always above it.
+        let found = detector(Baseline::Ema { alpha: 0.5 }).detect(&series);
+        assert_eq!(indices(&found), [0]);
+    }
+
+    #[test]
+    fn mean_over_days() {
+        let series = daily(&[10.0, 20.0, 30.0, 61.0]);
+        let found = detector(Baseline::Mean {
+            window: Window::Days(2),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines, [0.0, 10.0, 15.0, 25.0]);
+        assert_eq!(indices(&found), [3]);
+        assert_eq!(found.notable[0].samples, 2);
+    }
+
+    #[test]
+    fn bursts_count_once_per_day() {
+        let start = datetime!(2020-01-01 12:00 UTC);
+        // A month of one tweet a day,
+        let mut series: Vec<Sample> = (0..30)
+            .map(|day| Sample {
+                time: start + time::Duration::days(day),
+                value: 20.0,
+            })
+            .collect();
+        // then live-tweeting an event that does really well,
+        let event = start + time::Duration::days(30);
+        series.extend((0..50).map(|i| Sample {
+            time: event + time::Duration::minutes(i),
+            value: 100.0,
+        }));
+        // followed by a tweet that did better than usual.
+        series.push(Sample {
+            time: start + time::Duration::days(31),
+            value: 60.0,
+        });
+        let last = series.len() - 1;
+
+        let found = detector(Baseline::Mean {
+            window: Window::Days(30),
+        })
+        .detect(&series);
+        // 29 days at 20 and one day at 100.
+        assert!((found.baselines[last] - 680.0 / 30.0).abs() < 1e-9);
+        let pick = found.notable.iter().find(|n| n.index == last).unwrap();
+        assert_eq!(pick.samples, 79);
+
+        // Whereas going tweet by tweet, the event drowns out the month before it.
+        let found = detector(Baseline::Mean {
+            window: Window::Tweets(79),
+        })
+        .detect(&series);
+        assert!(found.baselines[last] > 70.0);
+        assert!(!indices(&found).contains(&last));
+    }
+
+    #[test]
+    fn explain() {
+        let pick = |baseline, score, samples| Notable {
+            index: 0,
+            value: 100.0,
+            baseline,
+            samples,
+            score,
+        };
+        assert_eq!(
+            detector(Baseline::default()).explain(&pick(20.0, 5.0, 12)),
+            "5.0× the average of the previous 30 days (12 tweets), which was 20.00"
+        );
+        assert_eq!(
+            detector(Baseline::Median {
+                window: Window::Tweets(1)
+            })
+            .explain(&pick(40.0, 2.5, 1)),
+            "2.5× the median of the previous tweet, which was 40.00"
+        );
+        assert_eq!(
+            detector(Baseline::Ema { alpha: 0.5 }).explain(&pick(0.0, f64::INFINITY, 0)),
+            "The moving average (alpha 0.5) was 0"
+        );
+        assert_eq!(
+            detector(Baseline::ZScore {
+                window: Window::Days(7)
+            })
+            .explain(&pick(20.0, 3.25, 1)),
+            "3.2 standard deviations above the average of the previous 7 days (1 tweet), which was \
+             20.00"
+        );
+        assert_eq!(
+            detector(Baseline::ZScore {
+                window: Window::Days(7)
+            })
+            .explain(&pick(20.0, f64::INFINITY, 4)),
+            "The average of the previous 7 days (4 tweets) was 20.00, and all of them scored the \
+             same"
+        );
+    }
+
+    #[test]
+    fn zscore() {
+        // Alternating 10 and 30 has a mean of 20 and a standard deviation of 10.
+        let series = daily(&[10.0, 30.0, 10.0, 30.0, 45.0, 50.0]);
+        let found = detector(Baseline::ZScore {
+            window: Window::Tweets(4),
+        })
+        .detect(&series);
+        assert_eq!(found.baselines[4], 20.0);
+        // 45 is 2.5 standard deviations above the mean, so it's notable. With the 45 in the window
+        // the mean is 28.75 and the spread is larger, so 50 isn't.
+        let spike = found
+            .notable
+            .iter()
+            .find(|n| n.index == 4)
+            .expect("45 is notable");
+        assert_eq!(spike.score, 2.5);
+        assert!(!indices(&found).contains(&5));
+    }
+
+    #[test]
+    fn zscore_without_spread() {
+        let series = daily(&[20.0, 20.0, 20.0, 20.0, 21.0, 19.0]);
+        let found = detector(Baseline::ZScore {
+            window: Window::Tweets(3),
+        })
+        .detect(&series);
+        // Anything above a flat line is infinitely many standard deviations above it.
+        assert_eq!(indices(&found), [4]);
+        assert_eq!(found.notable[0].score, f64::INFINITY);
+    }
+
+    #[test]
+    fn config() {
+        #[derive(Deserialize)]
+        struct Config {
+            a: Baseline,
+            b: Baseline,
+            c: Baseline,
+        }
+        let config: Config = toml::from_str(
+            r#"
+            a = { kind = "ema", alpha = 0.25 }
+            b = { kind = "median", window = { tweets = 20 } }
+            c = { kind = "zscore", window = { days = 90 } }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.a, Baseline::Ema { alpha: 0.25 });
+        assert_eq!(
+            config.b,
+            Baseline::Median {
+                window: Window::Tweets(20)
+            }
+        );
+        assert_eq!(
+            config.c,
+            Baseline::ZScore {
+                window: Window::Days(90)
+            }
+        );
+    }
+}
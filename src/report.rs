@@ -0,0 +1,290 @@
+//! Rendering of the generated report page.
+//!
+//! The page is rendered with [MiniJinja](https://docs.rs/minijinja), a Jinja2-like template
+//! engine. A default theme is built in, but any of its templates can be replaced by putting a file
+//! with the same name in a directory and passing that with `--template`. Templates that aren't
+//! overridden fall back to the built-in ones, so it's fine to just change `style.css`.
+//!
+//! The built-in templates are:
+//!
+//!  - `index.html`: the page itself, which includes all the others.
+//!  - `style.css`: the page's stylesheet, included inline into `index.html`.
+//!  - `users.html`: a single list of accounts, rendered once for each of `user_groups`.
+//!  - `tweet.html`: a single tweet, rendered from its [`Card`].
+//!  - `widgets.html`: the scripts that replace tweets with Twitter's embedded ones, only included
+//!    with the `embed` renderer.
+//!  - `report.md`: the whole report as Markdown, used instead of all of the above with
+//!    `--format markdown`.
+//!  - `dms.html`: the separate page of DM statistics and transcripts written with `--dms`. It is
+//!    rendered with the fields of [`dms::Context`] instead.
+//!
+//! Every other template is rendered with the fields of [`Context`] as variables. In addition, the
+//! `date` filter turns a timestamp (like [`Card::created`]) into just its date.
+
+use crate::api;
+use anyhow::Context as _;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+pub mod charts;
+pub mod dms;
+pub mod json;
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("index.html", include_str!("report/default/index.html")),
+    ("style.css", include_str!("report/default/style.css")),
+    ("users.html", include_str!("report/default/users.html")),
+    ("tweet.html", include_str!("report/default/tweet.html")),
+    ("widgets.html", include_str!("report/default/widgets.html")),
+    ("report.md", include_str!("report/default/report.md")),
+    ("dms.html", include_str!("report/default/dms.html")),
+];
+
+/// How tweets are shown in the report.
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    /// Entirely from local data, without loading anything from Twitter.
+    Static,
+    /// Using Twitter's embedded tweets, with local data as a fallback.
+    Embed,
+}
+
+/// Everything templates have access to.
+#[derive(Debug, Serialize)]
+pub struct Context {
+    /// The username of the account the archive belongs to.
+    pub me: String,
+    /// Which renderer was picked, as either `"static"` or `"embed"`.
+    pub renderer: Renderer,
+    /// The period the tweet lists are limited to (like `2021` or `2021-Q3`), if any.
+    pub period: Option<String>,
+    /// All the computed lists, keyed by their id (like `top_tweets` or `neat_followers`).
+    ///
+    /// Lists of tweets hold tweet ids, and lists of accounts hold usernames. Either way, the
+    /// entries are strings.
+    pub lists: HashMap<String, Vec<String>>,
+    /// The lists of tweets to show, in order.
+    pub tweet_groups: Vec<Group>,
+    /// The lists of accounts to show, in order.
+    pub user_groups: Vec<Group>,
+    /// The number of liked tweets from each year.
+    pub like_volume: BTreeMap<i32, usize>,
+    /// What we know about each tweet in `lists`, keyed by the tweet's id.
+    pub cards: HashMap<String, Card>,
+    /// Why tweets were picked for lists of notable tweets, keyed by list id and then tweet id.
+    pub explanations: HashMap<String, HashMap<String, String>>,
+    /// The threads started by tweets in `lists`, keyed by the id of their first tweet.
+    ///
+    /// Each has a `length`, and `metrics` with the same fields as those of cards, but summed over
+    /// the whole thread.
+    pub threads: HashMap<String, ThreadSummary>,
+    /// The Twitter API's records of the accounts in `lists`, keyed by username, for those we
+    /// have them for.
+    ///
+    /// Each has the fields `id`, `username`, and `public_metrics`, with the latter holding
+    /// `followers_count` and `following_count`.
+    pub users: HashMap<String, api::User>,
+    /// Charts giving an overview of the data, each with an `id`, a `title`, and the chart itself
+    /// as an `svg` element.
+    pub charts: Vec<charts::Chart>,
+}
+
+/// A list shown in the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    /// The key of the list in [`Context::lists`].
+    pub id: String,
+    pub title: String,
+}
+
+impl Group {
+    pub fn new(id: &str, title: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+        }
+    }
+}
+
+/// An entry in one of the computed lists.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    Tweet(TweetEntry),
+    User(UserEntry),
+}
+
+impl Entry {
+    /// The tweet id or username the entry is for, which is how templates refer to it.
+    pub fn key(&self) -> String {
+        match self {
+            Entry::Tweet(t) => t.id.to_string(),
+            Entry::User(u) => u.username.clone(),
+        }
+    }
+}
+
+/// A tweet in one of the computed lists.
+#[derive(Debug, Clone, Serialize)]
+pub struct TweetEntry {
+    #[serde(with = "api::u64_but_str")]
+    pub id: u64,
+    #[serde(flatten)]
+    pub card: Card,
+    /// Whatever the list is ranked by, if anything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// For tweets that were notable at the time, the baseline their score was compared to (see
+    /// [`crate::notable`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average: Option<f64>,
+    /// For tweets that were notable at the time, why, in words.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    /// For your tweets that started a thread, the thread as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<ThreadSummary>,
+}
+
+impl TweetEntry {
+    /// An entry for the tweet with the given `id`.
+    ///
+    /// Unless `card` already has a `url`, it links to the tweet as posted by `card.author`.
+    pub fn new(id: u64, mut card: Card) -> Self {
+        if card.url.is_empty() {
+            card.url = format!(
+                "https://twitter.com/{}/status/{}",
+                card.author.as_deref().unwrap_or("i"),
+                id
+            );
+        }
+        Self {
+            id,
+            card,
+            score: None,
+            average: None,
+            explanation: None,
+            thread: None,
+        }
+    }
+}
+
+/// A thread of your tweets, summarized next to the tweet that started it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadSummary {
+    /// How many tweets are in the thread, including the first one.
+    pub length: usize,
+    /// The sum of the metrics of every tweet in the thread.
+    pub metrics: api::PublicTweetMetrics,
+}
+
+/// An account in one of the computed lists.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserEntry {
+    pub username: String,
+    pub url: String,
+    /// Only known for followers and followed accounts, and only if we talked to the Twitter API.
+    pub user: Option<api::User>,
+    /// Whatever the list is ranked by.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+impl UserEntry {
+    pub fn new(username: String, user: Option<api::User>) -> Self {
+        Self {
+            url: format!("https://twitter.com/{}", username),
+            username,
+            user,
+            score: None,
+        }
+    }
+}
+
+/// What we know locally about a tweet shown in the report.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Card {
+    /// Where the tweet is on Twitter.
+    ///
+    /// This is usually the tweet by `author` with the tweet's id, except for your old retweets of
+    /// tweets whose id isn't in the archive, which link to the retweet instead.
+    pub url: String,
+    /// Only unknown for likes of tweets whose link doesn't say who wrote them.
+    pub author: Option<String>,
+    /// We only know the text of your own tweets and of tweets you liked.
+    pub text: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created: Option<time::OffsetDateTime>,
+    /// We only know the metrics of your own tweets.
+    ///
+    /// Has the fields `like_count`, `retweet_count`, `reply_count`, and `quote_count`.
+    pub metrics: Option<api::PublicTweetMetrics>,
+    /// Photos and videos from the archive.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<Thumbnail>,
+}
+
+/// A photo or video from the archive, as it should be referenced from the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    /// Either a path relative to the report, or a `data:` URI.
+    pub src: String,
+    pub video: bool,
+}
+
+/// Render the report using the named template (like `index.html`), using templates from
+/// `template_dir` where present.
+///
+/// `context` is usually a [`Context`], except for `dms.html` (see [`dms::Context`]).
+pub fn render(
+    name: &str,
+    context: &impl Serialize,
+    template_dir: Option<&Path>,
+) -> anyhow::Result<String> {
+    let mut env = minijinja::Environment::new();
+    env.add_filter("date", |ts: String| -> String {
+        // Timestamps are RFC 3339, so the date is always the first part.
+        ts.split_once('T')
+            .map_or(ts.clone(), |(date, _)| date.to_string())
+    });
+
+    let dir = template_dir.map(Path::to_path_buf);
+    env.set_loader(move |name| {
+        if let Some(path) = dir.as_deref().and_then(|dir| template_path(dir, name)) {
+            match std::fs::read_to_string(&path) {
+                Ok(source) => return Ok(Some(source)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(minijinja::Error::new(
+                        minijinja::ErrorKind::InvalidOperation,
+                        format!("could not read template {}", path.display()),
+                    )
+                    .with_source(e))
+                }
+            }
+        }
+        Ok(BUILTIN
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, source)| source.to_string()))
+    });
+
+    let template = env
+        .get_template(name)
+        .with_context(|| format!("load {} template", name))?;
+    template
+        .render(context)
+        .with_context(|| format!("render {} template", name))
+}
+
+/// Where the template with the given name would be in `dir`, unless the name tries to escape it.
+fn template_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    if name.components().all(|c| matches!(c, Component::Normal(_))) {
+        Some(dir.join(name))
+    } else {
+        None
+    }
+}
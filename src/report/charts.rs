@@ -0,0 +1,381 @@
+//! Charts for the report, rendered as self-contained SVG.
+//!
+//! Everything here produces a complete `<svg>` element as a string, so charts can be put straight
+//! into the page without any scripts. Hovering over a data point shows what it is, and points that
+//! correspond to tweets link to them.
+
+use serde::Serialize;
+use std::fmt::Write;
+
+const WIDTH: f64 = 900.0;
+const HEIGHT: f64 = 320.0;
+/// Room for axis labels on the left and bottom, and a little breathing room on the other sides.
+const LEFT: f64 = 60.0;
+const BOTTOM: f64 = 30.0;
+const PAD: f64 = 10.0;
+
+/// A chart to include in the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Chart {
+    pub id: String,
+    pub title: String,
+    /// The chart as an `<svg>` element.
+    pub svg: String,
+}
+
+/// A tweet in the engagement scatter plot.
+#[derive(Debug, Clone)]
+pub struct Point {
+    pub id: u64,
+    pub url: String,
+    pub created: time::OffsetDateTime,
+    pub value: f64,
+    /// The baseline `value` was compared to when looking for notable tweets.
+    pub average: f64,
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn open_svg(out: &mut String, label: &str) {
+    write!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="100%" role="img" aria-label="{label}" font-family="sans-serif" font-size="11">"#,
+        w = WIDTH,
+        h = HEIGHT,
+        label = escape(label),
+    )
+    .unwrap();
+    // The axes.
+    write!(
+        out,
+        r##"<path d="M{l},{t} V{b} H{r}" fill="none" stroke="#888"/>"##,
+        l = LEFT,
+        t = PAD,
+        b = HEIGHT - BOTTOM,
+        r = WIDTH - PAD,
+    )
+    .unwrap();
+}
+
+/// Map a value on a log scale, so that a few very popular tweets don't squash all the others.
+fn log(v: f64) -> f64 {
+    (1.0 + v.max(0.0)).log10()
+}
+
+/// Where on the y axis a (log-scaled) value goes, given the largest value.
+fn y_for(v: f64, max: f64) -> f64 {
+    let span = HEIGHT - BOTTOM - PAD;
+    if max <= 0.0 {
+        return HEIGHT - BOTTOM;
+    }
+    HEIGHT - BOTTOM - span * v / max
+}
+
+/// Label the y axis at 0 and powers of ten up to `max`, which is log-scaled.
+fn log_ticks(out: &mut String, max: f64) {
+    let ticks = std::iter::once(0).chain(std::iter::successors(Some(1u64), |v| v.checked_mul(10)));
+    for v in ticks.take_while(|&v| log(v as f64) <= max) {
+        write!(
+            out,
+            r##"<text x="{x}" y="{y:.1}" text-anchor="end" dy="0.3em" fill="#555">{v}</text>"##,
+            x = LEFT - 5.0,
+            y = y_for(log(v as f64), max),
+        )
+        .unwrap();
+    }
+}
+
+/// A scatter plot of tweets over time, along with the baseline they were compared to.
+///
+/// The y axis is logarithmic. Returns `None` if there are no points to plot.
+pub fn scatter(label: &str, points: &[Point]) -> Option<String> {
+    let first = points.iter().map(|p| p.created).min()?;
+    let last = points.iter().map(|p| p.created).max()?;
+    let span = (last - first).as_seconds_f64().max(1.0);
+    let x_for =
+        |t: time::OffsetDateTime| LEFT + (WIDTH - LEFT - PAD) * (t - first).as_seconds_f64() / span;
+    let max = points
+        .iter()
+        .map(|p| log(p.value).max(log(p.average)))
+        .fold(0.0, f64::max);
+
+    let mut out = String::new();
+    open_svg(&mut out, label);
+    log_ticks(&mut out, max);
+
+    // Mark the start of each year along the x axis.
+    for year in (first.year() + 1)..=last.year() {
+        let start = time::Date::from_calendar_date(year, time::Month::January, 1)
+            .expect("January 1st exists")
+            .midnight()
+            .assume_utc();
+        let x = x_for(start);
+        write!(
+            out,
+            r##"<line x1="{x:.1}" x2="{x:.1}" y1="{t}" y2="{b}" stroke="#eee"/><text x="{x:.1}" y="{ty}" text-anchor="middle" fill="#555">{year}</text>"##,
+            t = PAD,
+            b = HEIGHT - BOTTOM,
+            ty = HEIGHT - BOTTOM + 15.0,
+        )
+        .unwrap();
+    }
+
+    for p in points {
+        write!(
+            out,
+            r##"<a href="{url}"><circle cx="{x:.1}" cy="{y:.1}" r="2.5" fill="rgb(29, 155, 240)" fill-opacity="0.6"><title>{id}: {v} ({date})</title></circle></a>"##,
+            url = escape(&p.url),
+            x = x_for(p.created),
+            y = y_for(log(p.value), max),
+            id = p.id,
+            v = p.value,
+            date = p.created.date(),
+        )
+        .unwrap();
+    }
+
+    // The baseline goes on top so it's not hidden by the points.
+    let mut sorted: Vec<_> = points.iter().collect();
+    sorted.sort_by_key(|p| p.created);
+    let mut line = String::new();
+    for (i, p) in sorted.iter().enumerate() {
+        write!(
+            line,
+            "{}{:.1},{:.1}",
+            if i == 0 { "M" } else { " L" },
+            x_for(p.created),
+            y_for(log(p.average), max)
+        )
+        .unwrap();
+    }
+    write!(
+        out,
+        r##"<path d="{line}" fill="none" stroke="rgb(249, 24, 128)" stroke-width="1.5"><title>baseline</title></path>"##,
+    )
+    .unwrap();
+
+    out.push_str("</svg>");
+    Some(out)
+}
+
+/// A bar chart with one labeled bar per entry.
+///
+/// Returns `None` if there are no bars.
+pub fn histogram(label: &str, bars: &[(String, usize)]) -> Option<String> {
+    if bars.is_empty() {
+        return None;
+    }
+    let max = bars.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1) as f64;
+    let slot = (WIDTH - LEFT - PAD) / bars.len() as f64;
+    let span = HEIGHT - BOTTOM - PAD;
+    // Only label as many bars as fit.
+    let every = ((bars.len() as f64 * 50.0 / (WIDTH - LEFT - PAD)).ceil() as usize).max(1);
+
+    let mut out = String::new();
+    open_svg(&mut out, label);
+    write!(
+        out,
+        r##"<text x="{x}" y="{t}" text-anchor="end" dy="0.6em" fill="#555">{max}</text><text x="{x}" y="{b}" text-anchor="end" fill="#555">0</text>"##,
+        x = LEFT - 5.0,
+        t = PAD,
+        b = HEIGHT - BOTTOM,
+    )
+    .unwrap();
+    for (i, (name, n)) in bars.iter().enumerate() {
+        let h = span * *n as f64 / max;
+        let x = LEFT + slot * i as f64;
+        write!(
+            out,
+            r##"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="rgb(29, 155, 240)"><title>{name}: {n}</title></rect>"##,
+            x = x + slot * 0.1,
+            y = HEIGHT - BOTTOM - h,
+            w = slot * 0.8,
+            name = escape(name),
+        )
+        .unwrap();
+        if i % every == 0 {
+            write!(
+                out,
+                r##"<text x="{x:.1}" y="{y}" text-anchor="middle" fill="#555">{name}</text>"##,
+                x = x + slot / 2.0,
+                y = HEIGHT - BOTTOM + 15.0,
+                name = escape(name),
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("</svg>");
+    Some(out)
+}
+
+/// Count how many of the given times fall in each month, including months with none.
+pub fn per_month(times: impl IntoIterator<Item = time::OffsetDateTime>) -> Vec<(String, usize)> {
+    let mut counts = std::collections::BTreeMap::new();
+    for t in times {
+        *counts.entry((t.year(), t.month() as u8)).or_insert(0) += 1;
+    }
+    let (first, last) = match (counts.keys().next(), counts.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let mut months = Vec::new();
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        let n = counts.get(&(year, month)).copied().unwrap_or(0);
+        months.push((format!("{}-{:02}", year, month), n));
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+    }
+    months
+}
+
+/// Bucket counts (like follower counts) by order of magnitude.
+pub fn by_magnitude(counts: impl IntoIterator<Item = usize>) -> Vec<(String, usize)> {
+    let mut buckets: Vec<usize> = Vec::new();
+    for n in counts {
+        let b = if n == 0 {
+            0
+        } else {
+            (n as f64).log10().floor() as usize + 1
+        };
+        if buckets.len() <= b {
+            buckets.resize(b + 1, 0);
+        }
+        buckets[b] += 1;
+    }
+    buckets
+        .into_iter()
+        .enumerate()
+        .map(|(b, n)| {
+            let name = match b {
+                0 => "0".to_string(),
+                1 => "1-9".to_string(),
+                b => format!(
+                    "{}-{}",
+                    10usize.pow(b as u32 - 1),
+                    10usize.pow(b as u32) - 1
+                ),
+            };
+            (name, n)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn bars(names_and_counts: &[(&str, usize)]) -> Vec<(String, usize)> {
+        names_and_counts
+            .iter()
+            .map(|&(name, n)| (name.to_string(), n))
+            .collect()
+    }
+
+    #[test]
+    fn per_month_empty() {
+        assert!(per_month(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn per_month_fills_gaps_across_years() {
+        let months = per_month([
+            datetime!(2022-02-01 0:00 UTC),
+            datetime!(2021-11-30 23:59 UTC),
+            datetime!(2021-11-01 0:00 UTC),
+        ]);
+        assert_eq!(
+            months,
+            bars(&[
+                ("2021-11", 2),
+                ("2021-12", 0),
+                ("2022-01", 0),
+                ("2022-02", 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn by_magnitude_buckets() {
+        let buckets = by_magnitude([0, 5, 9, 10, 99, 100, 12345]);
+        assert_eq!(
+            buckets,
+            bars(&[
+                ("0", 1),
+                ("1-9", 2),
+                ("10-99", 2),
+                ("100-999", 1),
+                ("1000-9999", 0),
+                ("10000-99999", 1),
+            ])
+        );
+        assert!(by_magnitude(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn histogram_empty() {
+        assert_eq!(histogram("nothing", &[]), None);
+    }
+
+    #[test]
+    fn histogram_labels_all_bars_that_fit() {
+        let svg = histogram("few", &bars(&[("a", 1), ("b", 0), ("c", 3)])).unwrap();
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert_eq!(svg.matches(r#"text-anchor="middle""#).count(), 3);
+    }
+
+    #[test]
+    fn histogram_thins_labels() {
+        let many: Vec<_> = (0..100).map(|i| (i.to_string(), i)).collect();
+        let svg = histogram("many", &many).unwrap();
+        assert_eq!(svg.matches("<rect").count(), 100);
+        // 100 bars with 50px per label in 830px only leaves room for every 7th label.
+        assert_eq!(svg.matches(r#"text-anchor="middle""#).count(), 15);
+        assert!(svg.contains(">0</text>"));
+        assert!(svg.contains(">7</text>"));
+        assert!(!svg.contains(">1</text>"));
+    }
+
+    #[test]
+    fn y_for_range() {
+        assert_eq!(y_for(0.0, 2.0), HEIGHT - BOTTOM);
+        assert_eq!(y_for(2.0, 2.0), PAD);
+        // With nothing above zero, everything goes on the x axis rather than dividing by zero.
+        assert_eq!(y_for(0.0, 0.0), HEIGHT - BOTTOM);
+        assert_eq!(y_for(1.0, -1.0), HEIGHT - BOTTOM);
+    }
+
+    #[test]
+    fn scatter_all_zero() {
+        let point = |id, created| Point {
+            id,
+            url: format!("https://twitter.com/jonhoo/status/{}", id),
+            created,
+            value: 0.0,
+            average: 0.0,
+        };
+        let svg = scatter(
+            "zeros",
+            &[
+                point(1, datetime!(2021-12-31 0:00 UTC)),
+                point(2, datetime!(2022-01-02 0:00 UTC)),
+            ],
+        )
+        .unwrap();
+        assert!(!svg.contains("NaN"));
+        assert_eq!(svg.matches("<circle").count(), 2);
+        // The year boundary gets marked.
+        assert!(svg.contains(">2022</text>"));
+        assert_eq!(scatter("nothing", &[]), None);
+    }
+}
@@ -0,0 +1,53 @@
+//! The context the DM transcript page (`dms.html`) is rendered with, for use with `--dms`.
+//!
+//! Like the rest of the report, the page can be replaced by putting a `dms.html` in the
+//! `--template` directory. Since it holds private messages, the built-in page deliberately doesn't
+//! load anything from elsewhere, and a replacement probably shouldn't either.
+//!
+//! Names are usernames with a leading `@` where the Twitter API told us about the account, and
+//! `user <id>` otherwise. The `tojson` filter is the easiest way to get the data into a script,
+//! as the built-in page does.
+
+use serde::Serialize;
+
+/// Everything the `dms.html` template has access to.
+#[derive(Debug, Serialize)]
+pub struct Context {
+    /// The username of the account the archive belongs to.
+    pub me: String,
+    pub summary: Summary,
+    /// Every conversation, longest first.
+    pub transcripts: Vec<Transcript>,
+}
+
+/// Statistics across all conversations (see [`crate::dms::Stats`]).
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// Everyone you've exchanged messages with, as `[name, messages]` pairs, most messages first.
+    pub correspondents: Vec<(String, usize)>,
+    /// How long it usually took you to respond, like `3h`, if known.
+    pub my_response_time: Option<String>,
+    /// How long it usually took others to respond to you, if known.
+    pub their_response_time: Option<String>,
+    /// The number of messages sent each month, as `[YYYY-MM, messages]` pairs, busiest first.
+    pub busiest_months: Vec<(String, usize)>,
+}
+
+/// A single conversation.
+#[derive(Debug, Serialize)]
+pub struct Transcript {
+    /// The names of everyone in the conversation but you, separated by commas.
+    pub with: String,
+    /// Every message, oldest first.
+    pub lines: Vec<Line>,
+}
+
+/// A single message.
+#[derive(Debug, Serialize)]
+pub struct Line {
+    /// The name of the sender.
+    pub from: String,
+    /// When the message was sent.
+    pub at: String,
+    pub text: String,
+}
@@ -0,0 +1,107 @@
+//! The machine-readable version of the report, for use with `--format json`.
+//!
+//! The document is a single JSON object shaped like [`Document`]. Its `version` is bumped
+//! whenever a field is removed or changes meaning, but not when fields are added, so consumers
+//! should ignore fields they don't know about.
+//!
+//! All ids are strings, since they don't fit in the numbers many JSON parsers use.
+//!
+//! Each list is an array of entries, each of which has a `type` field that is either `tweet` or
+//! `user`:
+//!
+//!  - Tweets have `id`, `url`, `author`, `text`, `created` (RFC 3339), and `metrics` (with
+//!    `like_count`, `retweet_count`, `reply_count` and `quote_count`). `text`, `created`, and
+//!    `metrics` are `null` where unknown, which for `metrics` is any tweet that isn't yours.
+//!  - Users have `username`, `url`, and `user`, which holds the user's record from the Twitter API
+//!    (`id`, `username`, and `public_metrics`) if it was fetched, and `null` otherwise.
+//!
+//! The lists of your own tweets are `top_<metric>` and `notable_<metric>` for each metric in the
+//! config (see the `score` module), so with the default config they're `top_tweets`,
+//! `top_talked_about`, `top_shared`, `notable_tweets`, `notable_talked_about`, and
+//! `notable_shared`. Threads of your tweets are ranked the same way, by the first metric, in
+//! `top_threads` and `notable_threads`, where each entry is the tweet that started the thread.
+//!
+//! Your tweets that started a thread also have a `thread`, with its `length` in tweets and
+//! `metrics` summed over all of them. For entries in the thread lists, `score` and `average` are
+//! computed from those summed metrics.
+//!
+//! Entries of lists that are ranked also have a `score`, which is whatever they were ranked by.
+//! Entries of the lists of tweets that were notable at the time additionally have an `average`,
+//! which is the baseline the tweet was compared to (by default, the average score of the 30 days
+//! before the tweet was posted). Their `score` is how many times higher than that baseline the
+//! tweet scored, or, for metrics with a `zscore` baseline, how many standard deviations higher.
+//! They also have an `explanation`, which spells out the score, the baseline, and the window it
+//! was computed over, like "3.2× the average of the previous 30 days (12 tweets), which was
+//! 14.50".
+//!
+//! For plotting, `baselines` has the whole series behind the notable lists: for each metric, keyed
+//! by its name, every one of your tweets in the period with its `id`, `created`, `score` by that
+//! metric, and the `baseline` it was compared to, oldest first. The baseline is 0 for tweets that
+//! had nothing to be compared to, like your very first ones.
+
+use super::Entry;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The current version of the document format.
+pub const VERSION: u32 = 2;
+
+#[derive(Debug, Serialize)]
+pub struct Document {
+    /// Always [`VERSION`].
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub generated: time::OffsetDateTime,
+    pub account: Account,
+    /// The period the tweet lists are limited to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<Period>,
+    /// Every computed list, keyed by its id (like `top_tweets` or `neat_followers`).
+    pub lists: BTreeMap<String, Vec<Entry>>,
+    /// The number of liked tweets from each year.
+    pub like_volume: BTreeMap<i32, usize>,
+    pub relationships: Relationships,
+    /// Each tweet's score and baseline by each metric, keyed by metric name.
+    pub baselines: BTreeMap<String, Vec<BaselinePoint>>,
+}
+
+/// One of your tweets as seen when looking for notable tweets by some metric.
+#[derive(Debug, Serialize)]
+pub struct BaselinePoint {
+    #[serde(with = "crate::api::u64_but_str")]
+    pub id: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: time::OffsetDateTime,
+    pub score: f64,
+    /// What `score` was compared to (see [`crate::notable::Detection::baselines`]).
+    pub baseline: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Account {
+    #[serde(with = "crate::api::u64_but_str")]
+    pub id: u64,
+    pub username: String,
+    pub display_name: String,
+}
+
+/// A stretch of time the report covers, as given with `--since`, `--until`, or `--per`.
+#[derive(Debug, Serialize)]
+pub struct Period {
+    /// Like `2021`, `2021-Q3`, `2021-07`, or `2021-01-01 to 2021-06-30`.
+    pub label: String,
+    /// The first day of the period (YYYY-MM-DD), if it has one.
+    pub since: Option<String>,
+    /// The last day of the period (YYYY-MM-DD), if it has one.
+    pub until: Option<String>,
+}
+
+/// How many accounts fall into each kind of follow relationship.
+#[derive(Debug, Serialize)]
+pub struct Relationships {
+    pub followers: usize,
+    pub following: usize,
+    pub mutuals: usize,
+    pub not_following_back: usize,
+    pub fans: usize,
+}
@@ -0,0 +1,169 @@
+//! The metrics tweets are ranked by.
+//!
+//! Which tweets count as "top" or "notable" depends on what you value, so the metrics are read
+//! from a TOML file passed with `--config`. Each `[[metric]]` in it is a weighted sum of a tweet's
+//! public metrics (`likes`, `retweets`, `replies`, and `quotes`), and gets two lists in the
+//! report: `top_<name>` with the tweets that score the highest, and `notable_<name>` with the
+//! tweets that scored the highest compared to your other tweets at the time.
+//!
+//! Weights that are left out are zero. A tweet is only notable if its score is above
+//! `notable_floor` (10 by default) and more than `notable_factor` (2 by default) times the
+//! baseline. The baseline is the average score of the trailing 30 days unless the metric sets
+//! `baseline` to one of the other [`Baseline`]s, like
+//!
+//! ```toml
+//! baseline = { kind = "median", window = { tweets = 20 } }
+//! ```
+//!
+//! Without a config file, the metrics in [`DEFAULT`] are used.
+//!
+//! The config file can also have a `[filter]` table to pick which tweets to analyze; see
+//! [`crate::filter`].
+
+use crate::api::PublicTweetMetrics;
+use crate::filter::Filter;
+use crate::notable::{Baseline, Detector};
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The metrics used when no config file is given.
+pub const DEFAULT: &str = r#"
+[[metric]]
+name = "tweets"
+top = "Top tweets"
+notable = "Notable tweets (at the time)"
+likes = 1
+retweets = 2
+quotes = 3
+replies = 0.5
+
+[[metric]]
+name = "talked_about"
+top = "Most talked about tweets"
+notable = "Talked about tweets (at the time)"
+quotes = 2
+replies = 1
+
+[[metric]]
+name = "shared"
+top = "Most shared tweets"
+notable = "Widely shared tweets (at the time)"
+quotes = 2
+retweets = 1
+"#;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The metrics, in the order their lists appear in the report.
+    ///
+    /// The first one is also the one that's plotted.
+    #[serde(rename = "metric")]
+    pub metrics: Vec<Metric>,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::parse(DEFAULT).expect("default config is valid")
+    }
+}
+
+impl Config {
+    /// Read the config from the TOML file at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let s =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        Self::parse(&s).with_context(|| format!("parse {}", path.display()))
+    }
+
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(s)?;
+        anyhow::ensure!(!config.metrics.is_empty(), "no metrics defined");
+        let mut names = HashSet::new();
+        for metric in &config.metrics {
+            // The name ends up in list ids, which are used as HTML ids and JSON keys.
+            anyhow::ensure!(
+                !metric.name.is_empty()
+                    && metric
+                        .name
+                        .bytes()
+                        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'),
+                "metric name {:?} must be non-empty and only contain a-z, 0-9, and _",
+                metric.name
+            );
+            anyhow::ensure!(
+                names.insert(&metric.name),
+                "metric {:?} is defined more than once",
+                metric.name
+            );
+        }
+        Ok(config)
+    }
+}
+
+/// A named way to score tweets.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metric {
+    pub name: String,
+    /// The title of the `top_<name>` list.
+    pub top: Option<String>,
+    /// The title of the `notable_<name>` list.
+    pub notable: Option<String>,
+    #[serde(default)]
+    pub likes: f64,
+    #[serde(default)]
+    pub retweets: f64,
+    #[serde(default)]
+    pub replies: f64,
+    #[serde(default)]
+    pub quotes: f64,
+    #[serde(default = "default_floor")]
+    pub notable_floor: f64,
+    #[serde(default = "default_factor")]
+    pub notable_factor: f64,
+    #[serde(default)]
+    pub baseline: Baseline,
+}
+
+fn default_floor() -> f64 {
+    10.0
+}
+
+fn default_factor() -> f64 {
+    2.0
+}
+
+impl Metric {
+    pub fn score(&self, metrics: &PublicTweetMetrics) -> f64 {
+        self.likes * metrics.likes as f64
+            + self.retweets * metrics.retweets as f64
+            + self.replies * metrics.replies as f64
+            + self.quotes * metrics.quotations as f64
+    }
+
+    /// The detector for the `notable_<name>` list.
+    pub fn detector(&self) -> Detector {
+        Detector {
+            baseline: self.baseline,
+            floor: self.notable_floor,
+            factor: self.notable_factor,
+        }
+    }
+
+    pub fn top_title(&self) -> String {
+        self.top
+            .clone()
+            .unwrap_or_else(|| format!("Top tweets by {}", self.name))
+    }
+
+    pub fn notable_title(&self) -> String {
+        self.notable
+            .clone()
+            .unwrap_or_else(|| format!("Notable tweets by {} (at the time)", self.name))
+    }
+}
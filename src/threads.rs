@@ -0,0 +1,170 @@
+//! Stitching your self-replies back together into threads.
+//!
+//! The archive stores every tweet on its own, and a thread is just a chain of tweets that each
+//! reply to the one before. Threads can also branch if you reply to the same tweet of yours more
+//! than once, in which case all the branches are considered part of the same thread.
+
+use crate::api::{self, PublicTweetMetrics};
+use std::collections::{HashMap, HashSet};
+
+/// A tweet of yours along with all your replies to it, and your replies to those, and so on.
+#[derive(Debug, Clone)]
+pub struct Thread {
+    /// The tweet that started the thread.
+    pub root: u64,
+    /// When the root was posted.
+    pub created: time::OffsetDateTime,
+    /// Every tweet in the thread, including the root, in the order they were posted.
+    pub tweets: Vec<u64>,
+    /// The sum of the metrics of every tweet in the thread.
+    pub metrics: PublicTweetMetrics,
+}
+
+/// Find the threads among `tweets`, where `parent` gives the tweet a tweet is a self-reply to.
+///
+/// Only chains of at least two tweets count as threads. If the start of a thread is missing from
+/// `tweets` (say, because it was deleted), the earliest tweet that's still there is its root.
+/// Threads are returned in the order their roots were posted.
+pub fn reconstruct(tweets: &[api::Tweet], parent: impl Fn(u64) -> Option<u64>) -> Vec<Thread> {
+    let known: HashSet<u64> = tweets.iter().map(|t| t.id).collect();
+    let root_of = |mut id: u64| {
+        // Tweets can only reply to earlier tweets, so this can't loop forever unless the archive
+        // is broken, but we'd rather not hang if it is.
+        for _ in 0..tweets.len() {
+            match parent(id) {
+                Some(p) if known.contains(&p) => id = p,
+                _ => break,
+            }
+        }
+        id
+    };
+
+    let mut threads: HashMap<u64, Vec<&api::Tweet>> = HashMap::new();
+    for tweet in tweets {
+        threads.entry(root_of(tweet.id)).or_default().push(tweet);
+    }
+    let mut threads: Vec<Thread> = threads
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, mut members)| {
+            members.sort_unstable_by_key(|t| (t.created, t.id));
+            let mut metrics = PublicTweetMetrics {
+                retweets: 0,
+                replies: 0,
+                likes: 0,
+                quotations: 0,
+            };
+            for t in &members {
+                metrics.retweets += t.metrics.retweets;
+                metrics.replies += t.metrics.replies;
+                metrics.likes += t.metrics.likes;
+                metrics.quotations += t.metrics.quotations;
+            }
+            let created = members
+                .iter()
+                .find(|t| t.id == root)
+                .expect("root is in its own thread")
+                .created;
+            Thread {
+                root,
+                created,
+                tweets: members.iter().map(|t| t.id).collect(),
+                metrics,
+            }
+        })
+        .collect();
+    threads.sort_unstable_by_key(|t| (t.created, t.root));
+    threads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    /// A tweet posted `minutes` into the day, with `likes` likes and one of everything else.
+    fn tweet(id: u64, minutes: i64, likes: usize) -> api::Tweet {
+        api::Tweet {
+            id,
+            created: datetime!(2022-01-01 0:00 UTC) + time::Duration::minutes(minutes),
+            metrics: PublicTweetMetrics {
+                retweets: 1,
+                replies: 1,
+                likes,
+                quotations: 1,
+            },
+        }
+    }
+
+    /// Reconstruct threads where `replies` lists `(reply, parent)` pairs.
+    fn threads(tweets: &[api::Tweet], replies: &[(u64, u64)]) -> Vec<Thread> {
+        let parents: HashMap<u64, u64> = replies.iter().copied().collect();
+        reconstruct(tweets, |id| parents.get(&id).copied())
+    }
+
+    #[test]
+    fn single_tweets_are_not_threads() {
+        let tweets = [tweet(1, 0, 5), tweet(2, 1, 5)];
+        assert!(threads(&tweets, &[]).is_empty());
+    }
+
+    #[test]
+    fn chain() {
+        let tweets = [
+            tweet(1, 0, 10),
+            tweet(2, 1, 3),
+            tweet(3, 2, 1),
+            tweet(4, 3, 7),
+        ];
+        let found = threads(&tweets, &[(2, 1), (3, 2)]);
+        assert_eq!(found.len(), 1);
+        let thread = &found[0];
+        assert_eq!(thread.root, 1);
+        assert_eq!(thread.created, tweets[0].created);
+        assert_eq!(thread.tweets, vec![1, 2, 3]);
+        assert_eq!(thread.metrics.likes, 14);
+        assert_eq!(thread.metrics.retweets, 3);
+        assert_eq!(thread.metrics.replies, 3);
+        assert_eq!(thread.metrics.quotations, 3);
+    }
+
+    #[test]
+    fn branches() {
+        // 2 and 3 both reply to 1, and 4 continues the branch from 3.
+        let tweets = [
+            tweet(1, 0, 1),
+            tweet(2, 1, 1),
+            tweet(3, 2, 1),
+            tweet(4, 3, 1),
+        ];
+        let found = threads(&tweets, &[(2, 1), (3, 1), (4, 3)]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].root, 1);
+        assert_eq!(found[0].tweets, vec![1, 2, 3, 4]);
+        assert_eq!(found[0].metrics.likes, 4);
+    }
+
+    #[test]
+    fn deleted_root() {
+        // 1 started the thread, but has since been deleted.
+        let tweets = [tweet(2, 1, 1), tweet(3, 2, 1), tweet(4, 3, 1)];
+        let found = threads(&tweets, &[(2, 1), (3, 2), (4, 3)]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].root, 2);
+        assert_eq!(found[0].created, tweets[0].created);
+        assert_eq!(found[0].tweets, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn ordered_by_root() {
+        let tweets = [
+            tweet(1, 0, 1),
+            tweet(2, 1, 1),
+            tweet(3, 2, 1),
+            tweet(4, 3, 1),
+        ];
+        let found = threads(&tweets, &[(4, 2), (3, 1)]);
+        let roots: Vec<_> = found.iter().map(|t| t.root).collect();
+        assert_eq!(roots, vec![1, 2]);
+    }
+}